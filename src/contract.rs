@@ -1,51 +1,92 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{coin, to_json_binary, Addr, BankMsg, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, BankQuery, QuerierWrapper, QueryRequest, Response, StdError, StdResult, WasmMsg, WasmQuery};
+use cosmwasm_std::{coin, from_json, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, BankQuery, QuerierWrapper, QueryRequest, Response, StdError, StdResult, Storage, Uint128, WasmMsg};
 use cw2::set_contract_version;
-use cw721::Cw721ExecuteMsg;
+use cw_storage_plus::Bound;
+use cw20::{Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
+use cw721::{Cw721ExecuteMsg, Cw721ReceiveMsg};
 use sha2::{Sha256, Digest};
+use std::collections::BTreeMap;
 
-use cw721::{Cw721QueryMsg, OwnerOfResponse}; 
-// use cosmwasm_std::{to_json_binary, Addr, QuerierWrapper, StdResult, WasmQuery, QueryRequest};
-
+use crate::drand::{beacon_randomness, first_round_after, is_valid_public_key, verify_beacon};
 use crate::error::ContractError;
-use crate::msg::{GlobalResponse, GameResponse, WalletTicketResponse, AllGamesResponse, BalanceResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{GlobalState, GameState, GameStatus, GAME_STATE, GLOBAL_STATE, TICKET_STATUS, WALLET_TICKETS};
+use crate::msg::{AllowedCollectionsResponse, AllowedTokensResponse, ConfigUpdate, PendingOwnerResponse, RoleAssignment, RolesResponse, CreationCreditResponse, Cw20HookMsg, Cw2981Extension, Cw2981QueryMsg, RoyaltiesInfoResponse, DrandBeacon, NoisCallback, ProxyExecuteMsg, RaffleParams, ReceiveNftMsg, TreasuryAsset, TreasuryResponse, VaultPoolResponse, VaultResponse, VerifyDrawResponse, GlobalResponse, GameResponse, WalletTicketResponse, AllGamesResponse, BalanceResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::state::{AssetAmount, AssetInfo, DrawBeacon, DrawCallback, DrawMode, Prize, PrizeItem, TicketPrice, DrawRecord, Config, GlobalState, PayoutShare, PendingOwner, Role, GameState, GameStatus, VaultNft, VaultPool, CONFIG, DRAW_RECORDS, GAME_PAYOUTS, GAME_PROCEEDS, ALLOWED_COLLECTIONS, ALLOWED_TOKENS, CREATION_CREDITS, GAME_STATE, GLOBAL_STATE, LIABILITIES, PENDING_OWNER, PENDING_RANDOMNESS, ROLES, TICKET_STATUS, VAULT, WALLET_PAYMENTS, WALLET_TICKETS, REFUND_CLAIMED, RAFFLE_ACTIVE, RAFFLE_CANCELLED, RAFFLE_DRAWING, RAFFLE_ENDED, RAFFLE_NO_SALES, RAFFLE_REFUNDING};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:raffle";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// Time in milliseconds (same unit as end_time) the creator has after end_time to reveal the secret
+const REVEAL_WINDOW: u64 = 24 * 60 * 60 * 1000;
+
+// Time after end_time during which only keepers and the creator can finalize, afterwards anyone can
+const KEEPER_WINDOW: u64 = 24 * 60 * 60 * 1000;
+
+// Time in milliseconds a proxy game waits for its callback before operators can cancel it
+const RANDOMNESS_TIMEOUT: u64 = 24 * 60 * 60 * 1000;
+
+const MAX_PAYOUT_RECIPIENTS: usize = 10;
+
+// Page size of the allowed collections and roles queries
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    info: MessageInfo,
+    _info: MessageInfo,
     msg: InstantiateMsg,
 
 ) -> Result<Response, ContractError> {
-    let sender_str = info.sender.clone().to_string();
-    let data_to_hash = format!("{}{}", sender_str, "sei1j7ah3st8qjr792qjwtnjmj65rqhpedjqf9dnsddj");
-    let mut hasher = Sha256::new();
-    hasher.update(data_to_hash.as_bytes());
-    let result_hash = hasher.finalize();
-    let hex_encoded_hash = hex::encode(result_hash);
-
-    // Compare the generated hash with `msg.authkey`
-    if hex_encoded_hash != msg.authkey {
-        return Err(ContractError::Unauthorized {});
-    }
-
-    let global_state: GlobalState = GlobalState {
-        count: 0,
-        owner: msg.owner.clone()
+    let admin = deps.api.addr_validate(&msg.admin)?;
+    let fee_recipient = match msg.fee_recipient {
+        Some(fee_recipient) => deps.api.addr_validate(&fee_recipient)?,
+        None => admin.clone(),
+    };
+    let randomness_proxy = match msg.randomness_proxy {
+        Some(randomness_proxy) => Some(deps.api.addr_validate(&randomness_proxy)?),
+        None => None,
     };
+    let config = Config {
+        admin: Some(admin.clone()),
+        default_denom: msg.default_denom,
+        platform_fee_bps: msg.platform_fee_bps,
+        fee_recipient,
+        creation_enabled: true,
+        creation_fee: msg.creation_fee.filter(|fee| !fee.amount.is_zero()),
+        min_duration: msg.min_duration,
+        max_duration: msg.max_duration,
+        drand: msg.drand,
+        randomness_proxy,
+    };
+    validate_config(&config)?;
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    GLOBAL_STATE.save(deps.storage, &global_state)?;
+    CONFIG.save(deps.storage, &config)?;
+    GLOBAL_STATE.save(deps.storage, &GlobalState { count: 0, paused_since: None, paused_time: 0 })?;
 
     Ok(Response::new()
         .add_attribute("method", "instantiate")
-        .add_attribute("owner", msg.owner.to_string()))
+        .add_attribute("admin", admin.into_string()))
+}
+
+fn validate_config(config: &Config) -> Result<(), ContractError> {
+    if config.platform_fee_bps > 10000 {
+        return Err(ContractError::InvalidPlatformFee {});
+    }
+    if config.default_denom.is_empty() || config.min_duration > config.max_duration {
+        return Err(ContractError::InvalidConfig {});
+    }
+    // Drand games keep the drand config they were started with and have no refund fallback,
+    // so a config that can't verify a beacon would lock their prizes
+    if let Some(drand) = &config.drand {
+        if drand.period == 0 || !is_valid_public_key(drand.public_key.as_slice()) {
+            return Err(ContractError::InvalidDrandConfig {});
+        }
+    }
+    Ok(())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -56,39 +97,184 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::ReceiveNft { sender, token_id, msg } => try_receive_nft(deps, env, info, sender, token_id, msg),
-        ExecuteMsg::StartRaffle { ticket_price, total_ticket_count, nft_contract_addr, nft_token_id, collection_wallet, end_time } => 
-            try_start_raffle(deps, env, info, ticket_price, total_ticket_count, nft_contract_addr, nft_token_id, collection_wallet, end_time),
+        ExecuteMsg::ReceiveNft(msg) => try_receive_nft(deps, env, info, msg),
+        ExecuteMsg::Receive(msg) => try_receive_cw20(deps, env, info, msg),
+        ExecuteMsg::StartRaffle { params, prize } => try_start_direct_raffle(deps, env, info, params, prize),
+        ExecuteMsg::AddPrize { game_id, place, prize } => try_add_direct_prize(deps, env, info, game_id, place, prize),
         ExecuteMsg::EnterRaffle { game_id } => try_enter_raffle(deps, env, info, game_id),
-        ExecuteMsg::TransferTokensToCollectionWallet { amount, denom, collection_wallet_address } => try_transfer_tokens_to_collection_wallet(deps, env, info, amount, denom, collection_wallet_address),
-        ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id } => try_select_winner_and_transfer_nft_to_winner(deps, env, info, game_id),
+        ExecuteMsg::TransferTokensToCollectionWallet { amount, asset, collection_wallet_address } => try_transfer_tokens_to_collection_wallet(deps, env, info, amount, asset, collection_wallet_address),
+        ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id, secret, beacon } => try_select_winner_and_transfer_nft_to_winner(deps, env, info, game_id, secret, beacon),
+        ExecuteMsg::WithdrawFromVault { pool, contract_addr, token_id } => try_withdraw_from_vault(deps, env, info, pool, contract_addr, token_id),
+        ExecuteMsg::UpdateConfig(update) => try_update_config(deps, env, info, update),
+        ExecuteMsg::ProposeNewOwner { owner, expires_at } => try_propose_new_owner(deps, env, info, owner, expires_at),
+        ExecuteMsg::AcceptOwnership {} => try_accept_ownership(deps, env, info),
+        ExecuteMsg::RenounceOwnership {} => try_renounce_ownership(deps, env, info),
+        ExecuteMsg::Pause {} => try_set_paused(deps, env, info, None, true),
+        ExecuteMsg::Unpause {} => try_set_paused(deps, env, info, None, false),
+        ExecuteMsg::PauseGame { game_id } => try_set_paused(deps, env, info, Some(game_id), true),
+        ExecuteMsg::UnpauseGame { game_id } => try_set_paused(deps, env, info, Some(game_id), false),
+        ExecuteMsg::GrantRole { address, role } => try_update_role(deps, env, info, address, role, true),
+        ExecuteMsg::RevokeRole { address, role } => try_update_role(deps, env, info, address, role, false),
+        ExecuteMsg::AddAllowedCollection { contract_addr } => try_update_allowed_collection(deps, env, info, contract_addr, true),
+        ExecuteMsg::RemoveAllowedCollection { contract_addr } => try_update_allowed_collection(deps, env, info, contract_addr, false),
+        ExecuteMsg::AddAllowedToken { contract_addr } => try_update_allowed_token(deps, env, info, contract_addr, true),
+        ExecuteMsg::RemoveAllowedToken { contract_addr } => try_update_allowed_token(deps, env, info, contract_addr, false),
+        ExecuteMsg::DepositCreationCredit {} => try_deposit_creation_credit(deps, env, info),
+        ExecuteMsg::WithdrawCreationCredit { denom } => try_withdraw_creation_credit(deps, env, info, denom),
+        ExecuteMsg::CancelRaffle { game_id } => try_cancel_raffle(deps, env, info, game_id),
+        ExecuteMsg::ClaimRefund { game_id } => try_claim_refund(deps, env, info, game_id),
+        ExecuteMsg::NoisReceive { callback } => try_receive_randomness(deps, env, info, callback),
     }
 }
 
-// Pseudo-code for CW721 receiver function
+// CW721 receiver function, the NFT is already owned by this contract when it is called
 pub fn try_receive_nft(
-    _deps: DepsMut,
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: Cw721ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // info.sender is the cw721 contract, msg.sender the wallet that sent the NFT
+    let sender = deps.api.addr_validate(&msg.sender)?;
+
+    match from_json(&msg.msg)? {
+        ReceiveNftMsg::StartRaffle(params) => try_start_raffle(deps, env, sender, PrizeItem::Nft { contract_addr: info.sender, token_id: msg.token_id }, params),
+        ReceiveNftMsg::AddPrize { game_id, place } => try_add_prize(deps, env, sender, PrizeItem::Nft { contract_addr: info.sender, token_id: msg.token_id }, game_id, place),
+        ReceiveNftMsg::DepositToVault { pool } => try_deposit_to_vault(deps, sender, info.sender, msg.token_id, pool),
+    }
+}
+
+fn try_deposit_to_vault(
+    deps: DepsMut,
+    sender: Addr,
+    contract_addr: Addr,
+    token_id: String,
+    pool: String,
+) -> Result<Response, ContractError> {
+    // The hook can be called directly with a made up sender, only an allowed collection vouches for it
+    check_prize_allowed(deps.storage, &PrizeItem::Nft { contract_addr: contract_addr.clone(), token_id: token_id.clone() })?;
+    if !has_role(deps.storage, &sender, Role::Operator)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut vault_pool = VAULT.may_load(deps.storage, &pool)?.unwrap_or(VaultPool { nfts: vec![], reserved: 0 });
+    vault_pool.nfts.push(VaultNft { contract_addr: contract_addr.clone(), token_id: token_id.clone() });
+    VAULT.save(deps.storage, &pool, &vault_pool)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "deposit_to_vault")
+        .add_attribute("pool", pool)
+        .add_attribute("nft_contract_addr", contract_addr.into_string())
+        .add_attribute("token_id", token_id))
+}
+
+fn try_withdraw_from_vault(
+    deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
-    // Parameters might include the sender address, token ID, and any additional data
-    _sender: String,
+    info: MessageInfo,
+    pool: String,
+    contract_addr: String,
     token_id: String,
-    _msg: Binary,
 ) -> Result<Response, ContractError> {
+    if !has_role(deps.storage, &info.sender, Role::Operator)? {
+        return Err(ContractError::Unauthorized {});
+    }
 
-    // Logic to handle the received NFT, such as setting it as the prize for the raffle
+    let mut vault_pool = VAULT.load(deps.storage, &pool).map_err(|_| ContractError::WrongVaultPool {})?;
+    // Running mystery boxes must still be able to get an NFT out of the pool
+    if vault_pool.nfts.len() as u64 <= vault_pool.reserved {
+        return Err(ContractError::VaultPoolEmpty {});
+    }
+    let contract_addr = deps.api.addr_validate(&contract_addr)?;
+    let index = match vault_pool.nfts.iter().position(|nft| nft.contract_addr == contract_addr && nft.token_id == token_id) {
+        Some(index) => index,
+        None => return Err(ContractError::CantAccessPrize {}),
+    };
+    vault_pool.nfts.remove(index);
+    VAULT.save(deps.storage, &pool, &vault_pool)?;
 
-    // Additional logic as necessary, for example, parsing `msg` for any specific instructions
+    Ok(Response::new()
+        .add_message(transfer_nft_msg(&contract_addr, &token_id, &info.sender)?)
+        .add_attribute("action", "withdraw_from_vault")
+        .add_attribute("pool", pool)
+        .add_attribute("token_id", token_id))
+}
+
+// Sets an NFT of the pool aside for a mystery box, so the pool can't promise more NFTs than it holds
+fn reserve_vault_nft(storage: &mut dyn Storage, pool: &str, requester: &Addr) -> Result<(), ContractError> {
+    // The vault is stocked by operators, so only their raffles draw from it
+    if !has_role(storage, requester, Role::Operator)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut vault_pool = VAULT.load(storage, pool).map_err(|_| ContractError::WrongVaultPool {})?;
+    if vault_pool.nfts.len() as u64 <= vault_pool.reserved {
+        return Err(ContractError::VaultPoolEmpty {});
+    }
+    vault_pool.reserved += 1;
+    VAULT.save(storage, pool, &vault_pool)?;
+    Ok(())
+}
+
+fn release_vault_nft(storage: &mut dyn Storage, pool: &str) -> StdResult<()> {
+    let mut vault_pool = VAULT.load(storage, pool)?;
+    vault_pool.reserved -= 1;
+    VAULT.save(storage, pool, &vault_pool)
+}
 
-    Ok(Response::new().add_attribute("action", "receive_nft").add_attribute("token_id", token_id))
+// Takes the reserved NFT of a mystery box out of the pool, picked with the draw's randomness
+fn take_vault_nft(storage: &mut dyn Storage, pool: &str, seed: &[u8]) -> StdResult<PrizeItem> {
+    let mut vault_pool = VAULT.load(storage, pool)?;
+    let nft = vault_pool.nfts.swap_remove(winner_index_from_seed(seed, vault_pool.nfts.len() as u64) as usize);
+    vault_pool.reserved -= 1;
+    VAULT.save(storage, pool, &vault_pool)?;
+    Ok(PrizeItem::Nft { contract_addr: nft.contract_addr, token_id: nft.token_id })
+}
+
+// Fungible raffles created without a cw721 or cw20 hook
+fn try_start_direct_raffle(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    params: RaffleParams,
+    item: PrizeItem,
+) -> Result<Response, ContractError> {
+    let item = check_direct_prize(item, &info.funds)?;
+    try_start_raffle(deps, env, info.sender, item, params)
+}
+
+fn try_add_direct_prize(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    game_id: u64,
+    place: Option<u64>,
+    item: PrizeItem,
+) -> Result<Response, ContractError> {
+    let item = check_direct_prize(item, &info.funds)?;
+    try_add_prize(deps, env, info.sender, item, game_id, place)
 }
 
 // Function to get the current status of a game
-pub fn get_game_status(raffle_status: u8, end_time: u64, cur_time: u64) -> StdResult<GameStatus> {
+pub fn get_game_status(raffle_status: u8, end_time: u64, cur_time: u64, paused: bool) -> StdResult<GameStatus> {
 
-    if raffle_status == 0 {
+    if raffle_status == RAFFLE_ENDED {
         Ok(GameStatus::Ended)
     } 
+    else if raffle_status == RAFFLE_REFUNDING {
+        Ok(GameStatus::Refunding)
+    }
+    else if raffle_status == RAFFLE_DRAWING {
+        Ok(GameStatus::Drawing)
+    }
+    else if raffle_status == RAFFLE_NO_SALES {
+        Ok(GameStatus::NoSales)
+    }
+    else if raffle_status == RAFFLE_CANCELLED {
+        Ok(GameStatus::Cancelled)
+    }
+    else if paused {
+        Ok(GameStatus::Paused)
+    }
     else if cur_time * 1000 >= end_time {
         
         Ok(GameStatus::TimeOver)
@@ -98,66 +284,475 @@ pub fn get_game_status(raffle_status: u8, end_time: u64, cur_time: u64) -> StdRe
     }
 }
 
-fn can_transfer_nft(querier: &QuerierWrapper, nft_contract_addr: Addr, nft_token_id: String, operator: Addr) -> StdResult<bool> {
-    // Adjusted query to fetch ownership information
-    let owner_response: OwnerOfResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-        contract_addr: nft_contract_addr.into_string(),
-        msg: to_json_binary(&Cw721QueryMsg::OwnerOf {
-            token_id: nft_token_id,
-            // Include field for including expired items or not, based on your contract's requirements
-            include_expired: None, // This parameter depends on your CW721 version's API
-        })?,
-    }))?;
+fn transfer_nft_msg(nft_contract_addr: &Addr, token_id: &str, recipient: &Addr) -> StdResult<CosmosMsg> {
+    let transfer_msg = Cw721ExecuteMsg::TransferNft {
+        recipient: recipient.to_string(),
+        token_id: token_id.to_string(),
+    };
+
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: nft_contract_addr.to_string(),
+        msg: to_json_binary(&transfer_msg)?,
+        funds: vec![],
+    }))
+}
+
+fn send_asset_msg(asset: &AssetInfo, recipient: &Addr, amount: u128) -> StdResult<CosmosMsg> {
+    match asset {
+        AssetInfo::Native { denom } => Ok(CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![coin(amount, denom)],
+        })),
+        AssetInfo::Cw20 { contract_addr } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount: amount.into(),
+            })?,
+            funds: vec![],
+        })),
+    }
+}
+
+// Transfer of an escrowed prize item, a pot share isn't escrowed and is paid out of the proceeds instead
+fn escrowed_item_msg(item: &PrizeItem, recipient: &Addr) -> StdResult<Option<CosmosMsg>> {
+    match item {
+        PrizeItem::Nft { contract_addr, token_id } => Ok(Some(transfer_nft_msg(contract_addr, token_id, recipient)?)),
+        PrizeItem::Tokens { asset, amount } => Ok(Some(send_asset_msg(asset, recipient, amount.u128())?)),
+        PrizeItem::PotShare { .. } | PrizeItem::MysteryBox { .. } => Ok(None),
+    }
+}
+
+// Transfers of every escrowed item of a bundle, emitted in one response so the bundle moves as a whole
+fn escrowed_prize_msgs(storage: &mut dyn Storage, prize: &Prize, recipient: &Addr) -> StdResult<Vec<CosmosMsg>> {
+    let mut msgs = Vec::new();
+    for item in prize.items.iter() {
+        if let PrizeItem::Tokens { asset, amount } = item {
+            release_liability(storage, asset, amount.u128())?;
+        }
+        if let Some(msg) = escrowed_item_msg(item, recipient)? {
+            msgs.push(msg);
+        }
+    }
+    Ok(msgs)
+}
+
+// NFT of the first placement, the one a cw2981 royalty is asked for
+fn royalty_nft(game_state: &GameState) -> Option<(&Addr, &String)> {
+    game_state.prizes.first()?.items.iter().find_map(|item| match item {
+        PrizeItem::Nft { contract_addr, token_id } => Some((contract_addr, token_id)),
+        _ => None,
+    })
+}
+
+// Royalty owed on a sale of the prize NFT, none when the contract doesn't implement cw2981
+fn query_royalty(deps: Deps, game_state: &GameState, sale_price: Uint128) -> Option<(Addr, u128)> {
+    let (contract_addr, token_id) = royalty_nft(game_state)?;
+    let query = Cw2981QueryMsg::Extension {
+        msg: Cw2981Extension::RoyaltyInfo { token_id: token_id.clone(), sale_price },
+    };
+    let res: RoyaltiesInfoResponse = deps.querier.query_wasm_smart(contract_addr, &query).ok()?;
+    let receiver = deps.api.addr_validate(&res.address).ok()?;
+    Some((receiver, res.royalty_amount.u128()))
+}
+
+// Royalty on the whole pot first, then the platform fee, then the payout split weights (or all to the collection wallet).
+// Shares are rounded down and the remainder goes to the first recipient.
+fn payout_msgs(deps: Deps, fee_recipient: &Addr, game_state: &GameState, asset: &AssetInfo, amount: u128, sale_price: Uint128) -> StdResult<Vec<CosmosMsg>> {
+    let mut msgs = Vec::new();
+    let mut amount = amount;
+    if let Some((receiver, royalty)) = query_royalty(deps, game_state, sale_price) {
+        let royalty = std::cmp::min(royalty, amount);
+        if royalty > 0 {
+            msgs.push(send_asset_msg(asset, &receiver, royalty)?);
+            amount -= royalty;
+        }
+    }
+
+    let fee = Uint128::from(amount).multiply_ratio(game_state.platform_fee_bps, 10000u128).u128();
+    if fee > 0 {
+        msgs.push(send_asset_msg(asset, fee_recipient, fee)?);
+    }
+
+    let rest = amount - fee;
+    let split = if game_state.payout_split.is_empty() {
+        vec![PayoutShare { recipient: game_state.collection_wallet.clone(), weight: 1 }]
+    } else {
+        game_state.payout_split.clone()
+    };
+    let total_weight: u64 = split.iter().map(|share| share.weight).sum();
+    let mut shares: Vec<u128> = split.iter()
+        .map(|share| Uint128::from(rest).multiply_ratio(share.weight, total_weight).u128())
+        .collect();
+    shares[0] += rest - shares.iter().sum::<u128>();
+    for (share, share_amount) in split.iter().zip(shares) {
+        if share_amount > 0 {
+            msgs.push(send_asset_msg(asset, &share.recipient, share_amount)?);
+        }
+    }
+    Ok(msgs)
+}
+
+// Mystery boxes of a prize that won't be drawn give their NFT back to the pool
+fn release_mystery_boxes(storage: &mut dyn Storage, prize: &Prize) -> StdResult<()> {
+    for item in prize.items.iter() {
+        if let PrizeItem::MysteryBox { pool } = item {
+            release_vault_nft(storage, pool)?;
+        }
+    }
+    Ok(())
+}
+
+// Sends every escrowed prize back to the game owner
+fn return_prizes_msgs(storage: &mut dyn Storage, game_state: &GameState) -> StdResult<Vec<CosmosMsg>> {
+    let mut msgs = Vec::new();
+    for prize in game_state.prizes.iter() {
+        release_mystery_boxes(storage, prize)?;
+        msgs.extend(escrowed_prize_msgs(storage, prize, &game_state.owner)?);
+    }
+    Ok(msgs)
+}
+
+fn prize_item_label(item: &PrizeItem) -> String {
+    match item {
+        PrizeItem::Nft { contract_addr, token_id } => format!("{}/{}", contract_addr, token_id),
+        PrizeItem::Tokens { asset: AssetInfo::Native { denom }, amount } => format!("{}{}", amount, denom),
+        PrizeItem::Tokens { asset: AssetInfo::Cw20 { contract_addr }, amount } => format!("{}{}", amount, contract_addr),
+        PrizeItem::PotShare { bps } => format!("{}bps", bps),
+        PrizeItem::MysteryBox { pool } => format!("mystery_box:{}", pool),
+    }
+}
+
+fn prize_label(prize: &Prize) -> String {
+    prize.items.iter().map(prize_item_label).collect::<Vec<String>>().join(",")
+}
+
+// Prize items given in an execute message, native tokens must be sent along exactly
+fn check_direct_prize(item: PrizeItem, funds: &[Coin]) -> Result<PrizeItem, ContractError> {
+    match &item {
+        PrizeItem::Tokens { asset: AssetInfo::Native { denom }, amount } => {
+            match funds {
+                [sent] if sent.denom == *denom && sent.amount == *amount && !amount.is_zero() => {},
+                _ => return Err(ContractError::IncorrectFunds {}),
+            }
+        },
+        PrizeItem::PotShare { .. } | PrizeItem::MysteryBox { .. } => {
+            if !funds.is_empty() {
+                return Err(ContractError::IncorrectFunds {});
+            }
+        },
+        _ => return Err(ContractError::PrizeNotEscrowed {}),
+    }
+    Ok(item)
+}
+
+// Keeps scam collections, fake tokens and lookalike contracts out of the raffles
+fn check_prize_allowed(storage: &dyn Storage, item: &PrizeItem) -> Result<(), ContractError> {
+    match item {
+        PrizeItem::Nft { contract_addr, .. } if !ALLOWED_COLLECTIONS.has(storage, contract_addr.clone()) => Err(ContractError::CollectionNotAllowed {}),
+        PrizeItem::Tokens { asset: AssetInfo::Cw20 { contract_addr }, .. } if !ALLOWED_TOKENS.has(storage, contract_addr.clone()) => Err(ContractError::TokenNotAllowed {}),
+        _ => Ok(()),
+    }
+}
+
+// The pot can't be promised more than once
+fn check_pot_shares(prizes: &[Prize]) -> Result<(), ContractError> {
+    let mut total_bps = 0;
+    for item in prizes.iter().flat_map(|prize| prize.items.iter()) {
+        if let PrizeItem::PotShare { bps } = item {
+            if *bps == 0 {
+                return Err(ContractError::InvalidPotShare {});
+            }
+            total_bps += bps;
+        }
+    }
+    if total_bps > 10000 {
+        return Err(ContractError::InvalidPotShare {});
+    }
+    Ok(())
+}
+
+fn asset_amount(amounts: &[AssetAmount], asset: &AssetInfo) -> Uint128 {
+    amounts.iter().find(|existing| existing.asset == *asset).map_or(Uint128::zero(), |existing| existing.amount)
+}
+
+fn add_asset_amount(amounts: &mut Vec<AssetAmount>, asset: &AssetInfo, amount: u128) {
+    match amounts.iter_mut().find(|existing| existing.asset == *asset) {
+        Some(existing) => existing.amount += Uint128::from(amount),
+        None => amounts.push(AssetAmount { asset: asset.clone(), amount: Uint128::from(amount) }),
+    }
+}
+
+fn sub_asset_amount(amounts: &mut [AssetAmount], asset: &AssetInfo, amount: u128) -> StdResult<()> {
+    match amounts.iter_mut().find(|existing| existing.asset == *asset) {
+        Some(existing) => existing.amount = existing.amount.checked_sub(Uint128::from(amount))?,
+        None => return Err(StdError::generic_err("Asset amount not found")),
+    }
+    Ok(())
+}
+
+fn add_liability(storage: &mut dyn Storage, asset: &AssetInfo, amount: u128) -> StdResult<()> {
+    let mut liabilities = LIABILITIES.may_load(storage)?.unwrap_or_default();
+    add_asset_amount(&mut liabilities, asset, amount);
+    LIABILITIES.save(storage, &liabilities)
+}
+
+fn release_liability(storage: &mut dyn Storage, asset: &AssetInfo, amount: u128) -> StdResult<()> {
+    let mut liabilities = LIABILITIES.may_load(storage)?.unwrap_or_default();
+    sub_asset_amount(&mut liabilities, asset, amount)?;
+    LIABILITIES.save(storage, &liabilities)
+}
+
+fn asset_balance(querier: &QuerierWrapper, contract_addr: &Addr, asset: &AssetInfo) -> StdResult<Uint128> {
+    match asset {
+        AssetInfo::Native { denom } => Ok(querier.query_balance(contract_addr, denom)?.amount),
+        AssetInfo::Cw20 { contract_addr: token_addr } => {
+            let res: cw20::BalanceResponse = querier.query_wasm_smart(token_addr, &Cw20QueryMsg::Balance { address: contract_addr.to_string() })?;
+            Ok(res.balance)
+        },
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+// Folds a ticket purchase into the game's running entropy, so the seed isn't known before the sales.
+// The entropy is public, so this doesn't keep anyone who knows the external randomness from predicting the winner.
+fn mix_purchase_entropy(entropy: &str, buyer: &Addr, start_ticket_number: u64, ticket_count: u64, env: &Env) -> String {
+    let data = format!("{}{}{}{}{}{}", entropy, buyer, start_ticket_number, ticket_count, env.block.height, env.block.time.nanos());
+    sha256_hex(data.as_bytes())
+}
+
+// Whether the game sold enough to be drawn, creators don't have to give their NFT away for a handful of tickets.
+// The reserve values every ticket at the first price, whichever asset it was actually paid with.
+fn minimum_sales_reached(game_state: &GameState) -> bool {
+    let tickets_reached = game_state.min_tickets_sold.is_none_or(|min| game_state.sold_ticket_count >= min);
+    let reserve_reached = game_state.reserve_amount.is_none_or(|reserve| game_state.sold_ticket_count as u128 * game_state.prices[0].price.u128() >= reserve.u128());
+    tickets_reached && reserve_reached
+}
+
+// A commit-reveal game can't be drawn anymore once the creator missed the reveal window.
+// The creator can't reveal during a pause, so the window is extended by the time the game was paused.
+fn reveal_expired(storage: &dyn Storage, game_state: &GameState, env: &Env) -> StdResult<bool> {
+    let now = env.block.time.seconds() * 1000;
+    let deadline = game_state.end_time.saturating_add(REVEAL_WINDOW).saturating_add(game_paused_time(storage, game_state, now)?);
+    Ok(game_state.draw_mode == DrawMode::CommitReveal && deadline <= now)
+}
+
+fn winner_index_from_seed(seed: &[u8], mod_number: u64) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&seed[0..8]);
+    u64::from_be_bytes(bytes) % mod_number
+}
 
-    // Check if the contract is the owner or has been approved
-    Ok(owner_response.owner == operator || owner_response.approvals.iter().any(|approval| approval.spender == operator))
+// The winner is as unpredictable as the randomness. Drand rounds and proxy randomness are unknown to everyone until
+// after end_time, but a commit-reveal creator knows their secret: with a second wallet they can buy until the winner
+// suits them and they can refuse to reveal. That's why only operators can start commit-reveal games.
+// Only sold tickets take part, so any raffle with a sale has a real winner. A partial Fisher-Yates shuffle
+// over the sold tickets gives every placement a distinct ticket.
+fn select_winner_indexes(randomness: &str, entropy: &str, sold_ticket_count: u64, prize_count: u64) -> Vec<u64> {
+    let mut swapped: BTreeMap<u64, u64> = BTreeMap::new();
+    let mut winner_indexes = Vec::new();
+    for place in 0..std::cmp::min(prize_count, sold_ticket_count) {
+        let seed = Sha256::digest(format!("{}{}{}", randomness, entropy, place).as_bytes());
+        let index = place + winner_index_from_seed(&seed, sold_ticket_count - place);
+        winner_indexes.push(*swapped.get(&index).unwrap_or(&index));
+        swapped.insert(index, *swapped.get(&place).unwrap_or(&place));
+    }
+    winner_indexes
 }
 
+// Called with the prize already escrowed, so it can't be pulled out before the draw
 fn try_start_raffle(
     deps: DepsMut,
     env: Env,
-    info: MessageInfo,
-    ticket_price: u64,
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    ticket_price: u64,
-    total_ticket_count: u64,
-    nft_contract_addr: Addr,
-    nft_token_id: String,
-    collection_wallet: Addr,
-    end_time: u64
+    creator: Addr,
+    item: PrizeItem,
+    params: RaffleParams,
 ) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
     let mut global_state = GLOBAL_STATE.load(deps.storage)?;
-    // Check
-    if info.sender != global_state.owner {
-        return Err(ContractError::Unauthorized {  });
+    // Anyone holding a prize can start a raffle, operators even while creation is switched off and without a fee
+    let mut fee_msgs: Vec<CosmosMsg> = Vec::new();
+    let is_operator = has_role(deps.storage, &creator, Role::Operator)?;
+    if !is_operator {
+        if !config.creation_enabled {
+            return Err(ContractError::RaffleCreationDisabled {});
+        }
+        if let Some(fee) = config.creation_fee.clone() {
+            let key = (creator.clone(), fee.denom.clone());
+            let credit = CREATION_CREDITS.may_load(deps.storage, key.clone())?.unwrap_or_default();
+            if credit < fee.amount {
+                return Err(ContractError::InsufficientCreationCredit {});
+            }
+            CREATION_CREDITS.save(deps.storage, key, &(credit - fee.amount))?;
+            release_liability(deps.storage, &AssetInfo::Native { denom: fee.denom.clone() }, fee.amount.u128())?;
+            fee_msgs.push(send_asset_msg(&AssetInfo::Native { denom: fee.denom }, &config.fee_recipient, fee.amount.u128())?);
+        }
     }
-    
-    if !can_transfer_nft(&deps.querier, nft_contract_addr.clone(), nft_token_id.clone(), env.contract.address)? {
-        return Err(ContractError::CantAccessPrize {});
+    // Saturating, a max_duration close to u64::MAX just means there is no upper limit
+    let now = env.block.time.seconds() * 1000;
+    if params.end_time < now.saturating_add(config.min_duration) || params.end_time > now.saturating_add(config.max_duration) {
+        return Err(ContractError::InvalidRaffleDuration {});
+    }
+    let collection_wallet = deps.api.addr_validate(&params.collection_wallet)?;
+    check_prize_allowed(deps.storage, &item)?;
+    if let PrizeItem::MysteryBox { pool } = &item {
+        reserve_vault_nft(deps.storage, pool, &creator)?;
+    }
+    if let PrizeItem::Tokens { asset, amount } = &item {
+        add_liability(deps.storage, asset, amount.u128())?;
     }
+    let prize = Prize { items: vec![item] };
+    check_pot_shares(std::slice::from_ref(&prize))?;
+    if params.prices.is_empty() {
+        return Err(ContractError::InvalidTicketPrices {});
+    }
+    let mut prices: Vec<TicketPrice> = Vec::new();
+    for price in params.prices {
+        if price.price.is_zero() {
+            return Err(ContractError::ZeroTicketPrice {});
+        }
+        let asset = match price.asset {
+            AssetInfo::Native { denom } => AssetInfo::Native { denom },
+            AssetInfo::Cw20 { contract_addr } => AssetInfo::Cw20 { contract_addr: deps.api.addr_validate(contract_addr.as_str())? },
+        };
+        if prices.iter().any(|existing| existing.asset == asset) {
+            return Err(ContractError::InvalidTicketPrices {});
+        }
+        prices.push(TicketPrice { asset, price: price.price });
+    }
+    // A threshold the raffle can't reach even when sold out would only ever end in refunds
+    let sold_out_amount = Uint128::from(params.total_ticket_count).saturating_mul(prices[0].price);
+    if params.min_tickets_sold.unwrap_or(0) > params.total_ticket_count || params.reserve_amount.unwrap_or_default() > sold_out_amount {
+        return Err(ContractError::InvalidMinimumSales {});
+    }
+
+    let mut payout_split: Vec<PayoutShare> = Vec::new();
+    if let Some(split) = params.payout_split {
+        if split.is_empty() || split.len() > MAX_PAYOUT_RECIPIENTS {
+            return Err(ContractError::InvalidPayoutSplit {});
+        }
+        // The weights are summed at the draw, an overflow there would make the game impossible to draw
+        let mut total_weight: u64 = 0;
+        for share in split {
+            total_weight = total_weight.checked_add(share.weight).ok_or(ContractError::InvalidPayoutSplit {})?;
+            if share.weight == 0 {
+                return Err(ContractError::InvalidPayoutSplit {});
+            }
+            payout_split.push(PayoutShare { recipient: deps.api.addr_validate(share.recipient.as_str())?, weight: share.weight });
+        }
+    }
+
+    // Drand games and proxy games keep the randomness source they were started with
+    let (secret_hash, drand, randomness_proxy) = match params.draw_mode {
+        DrawMode::CommitReveal => {
+            // The creator can predict and steer the outcome, so only the operators are trusted with it
+            if !is_operator {
+                return Err(ContractError::CommitRevealNotAllowed {});
+            }
+            // The commitment has to be a sha256 digest, otherwise it could never be revealed
+            let secret_hash = params.secret_hash.unwrap_or_default().to_lowercase();
+            if hex::decode(&secret_hash).map(|bytes| bytes.len() != 32).unwrap_or(true) {
+                return Err(ContractError::InvalidSecretHash {});
+            }
+            (secret_hash, None, None)
+        },
+        DrawMode::Drand => (String::new(), Some(config.drand.ok_or(ContractError::DrandNotConfigured {})?), None),
+        DrawMode::Proxy => (String::new(), None, Some(config.randomness_proxy.ok_or(ContractError::ProxyNotConfigured {})?)),
+    };
     
-    let count_tmp = global_state.count.clone() + 1;
+    let count_tmp = global_state.count + 1;
     global_state.count += 1;
 
     // Assuming 1 represents 'active'
     let game_state: GameState = GameState {
-        raffle_status: 1,
+        raffle_status: RAFFLE_ACTIVE,
         sold_ticket_count: 0,
-        ticket_price: ticket_price,
-        total_ticket_count: total_ticket_count,
+        prices,
+        total_ticket_count: params.total_ticket_count,
+        prizes: vec![prize.clone()],
+        owner: creator,
+        collection_wallet,
+        payout_split,
+        platform_fee_bps: config.platform_fee_bps,
+        end_time: params.end_time,
+        min_tickets_sold: params.min_tickets_sold,
+        reserve_amount: params.reserve_amount,
+        draw_mode: params.draw_mode,
+        drand,
+        randomness_proxy,
+        entropy: format!("{}{}", count_tmp, secret_hash),
+        randomness_requested_at: None,
+        secret_hash,
+        paused_since: None,
+        paused_time: 0,
+        global_paused_offset: global_paused_time(&global_state, now),
+    };
+
+    GLOBAL_STATE.save(deps.storage, &global_state)?;
+    GAME_STATE.save(deps.storage, count_tmp , &game_state)?;
+    
+    Ok(Response::new()
+        .add_messages(fee_msgs)
+        .add_attribute("method", "start_raffle")
+        .add_attribute("status", "active")
+        .add_attribute("game_id", count_tmp.to_string())
+        .add_attribute("prize", prize_label(&prize)))
+}
 
-        nft_contract_addr: nft_contract_addr,
-        nft_token_id: nft_token_id,
+// Adds an escrowed prize item to the bundle of an existing placement, or as the next placement of a game
+fn try_add_prize(
+    deps: DepsMut,
+    _env: Env,
+    sender: Addr,
+    item: PrizeItem,
+    game_id: u64,
+    place: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut game_state = GAME_STATE.load(deps.storage, game_id)
+        .map_err(|_| ContractError::WrongGameId {})?;
 
+    if sender != game_state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if game_state.raffle_status != RAFFLE_ACTIVE {
+        return Err(ContractError::RaffleEnded {});
+    }
+    // Buyers must know what they are playing for
+    if game_state.sold_ticket_count > 0 {
+        return Err(ContractError::PrizesLocked {});
+    }
 
+    let place = match place {
+        Some(place) => {
+            match place.checked_sub(1).and_then(|index| game_state.prizes.get_mut(index as usize)) {
+                Some(prize) => prize.items.push(item.clone()),
+                None => return Err(ContractError::WrongPrizePlace {}),
+            }
+            place
+        },
+        None => {
+            game_state.prizes.push(Prize { items: vec![item.clone()] });
+            game_state.prizes.len() as u64
+        },
     };
+    check_pot_shares(&game_state.prizes)?;
+    check_prize_allowed(deps.storage, &item)?;
+    if let PrizeItem::MysteryBox { pool } = &item {
+        reserve_vault_nft(deps.storage, pool, &sender)?;
+    }
+    if let PrizeItem::Tokens { asset, amount } = &item {
+        add_liability(deps.storage, asset, amount.u128())?;
+    }
+    GAME_STATE.save(deps.storage, game_id, &game_state)?;
 
-    GLOBAL_STATE.save(deps.storage, &global_state)?;
-    GAME_STATE.save(deps.storage, count_tmp.clone() , &game_state)?;
-    
-    Ok(Response::new().add_attribute("method", "start_raffle").add_attribute("status", "active").add_attribute("game_id", count_tmp.to_string()))
+    Ok(Response::new()
+        .add_attribute("action", "add_prize")
+        .add_attribute("game_id", game_id.to_string())
+        .add_attribute("place", place.to_string())
+        .add_attribute("prize", prize_item_label(&item)))
 }
 
 fn try_enter_raffle(
@@ -166,12 +761,50 @@ fn try_enter_raffle(
     info: MessageInfo,
     game_id: u64,
 ) -> Result<Response, ContractError> {
+    // Exactly one coin, anything sent along in another denom would be stuck in the contract
+    let sent = match info.funds.as_slice() {
+        [sent] => sent,
+        _ => return Err(ContractError::IncorrectFunds {}),
+    };
+    enter_raffle(deps, env, info.sender, game_id, AssetInfo::Native { denom: sent.denom.clone() }, sent.amount.u128())
+}
 
-    match GAME_STATE.load(deps.storage, game_id.clone()) {
+// CW20 receiver function, info.sender is the token contract and msg.sender the buyer or raffle creator.
+// Anyone can call this directly with a made up msg.sender, so it is only trusted for prizes of allowed tokens.
+// A ticket bought through a fake token is paid in that token, which no game accepts unless its creator priced it so.
+fn try_receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_validate(&msg.sender)?;
+    let asset = AssetInfo::Cw20 { contract_addr: info.sender };
+
+    match from_json(&msg.msg)? {
+        Cw20HookMsg::EnterRaffle { game_id } => enter_raffle(deps, env, sender, game_id, asset, msg.amount.u128()),
+        Cw20HookMsg::StartRaffle(params) => try_start_raffle(deps, env, sender, PrizeItem::Tokens { asset, amount: msg.amount }, params),
+        Cw20HookMsg::AddPrize { game_id, place } => try_add_prize(deps, env, sender, PrizeItem::Tokens { asset, amount: msg.amount }, game_id, place),
+    }
+}
+
+fn enter_raffle(
+    deps: DepsMut,
+    env: Env,
+    buyer: Addr,
+    game_id: u64,
+    asset: AssetInfo,
+    sent_funds: u128,
+) -> Result<Response, ContractError> {
+
+    match GAME_STATE.load(deps.storage, game_id) {
         Ok(mut game_state) => {
-            if game_state.raffle_status.clone() == 0 {
+            if game_state.raffle_status != RAFFLE_ACTIVE {
                 return Err(ContractError::RaffleEnded {});
             }
+            if is_paused(deps.storage, &game_state)? {
+                return Err(ContractError::Paused {});
+            }
             if game_state.end_time <= env.block.time.seconds() * 1000 {
                 return Err(ContractError::RaffleTimeOver {  });
             }
@@ -180,36 +813,46 @@ fn try_enter_raffle(
                 return Err(ContractError::RaffleSoldOut {});
             }
 
-            // Simulate ticket purchase by verifying sent funds match the ticket price
-            let ticket_price = game_state.ticket_price as u128;
-            let sent_funds = info.funds.iter().find(|coin| coin.denom == "usei").map_or(0u128, |coin| coin.amount.u128());
-            if sent_funds.clone() < ticket_price.clone() {
+            // The ticket count is worked out from whichever supported asset was sent
+            let ticket_price = match game_state.prices.iter().find(|price| price.asset == asset) {
+                Some(price) => price.price.u128(),
+                None => return Err(ContractError::WrongPaymentAsset {}),
+            };
+            if sent_funds < ticket_price {
                 return Err(ContractError::IncorrectFunds {});
             }
-            let purchase_ticket_count = sent_funds.clone() / ticket_price.clone();
-            let real_purchase_ticket_count = std::cmp::min(purchase_ticket_count, game_state.total_ticket_count.clone() as u128 - game_state.sold_ticket_count.clone() as u128);
-            let start_ticket_number = game_state.sold_ticket_count.clone();
-            let key = (game_id.clone(), info.sender.clone());
+            let purchase_ticket_count = sent_funds / ticket_price;
+            let real_purchase_ticket_count = std::cmp::min(purchase_ticket_count, game_state.total_ticket_count as u128 - game_state.sold_ticket_count as u128);
+            let start_ticket_number = game_state.sold_ticket_count;
+            let key = (game_id, buyer.clone());
 
             // Retrieve the current list of tickets for the wallet and game ID, if it exists
             let mut tickets = WALLET_TICKETS.load(deps.storage, key.clone()).unwrap_or_else(|_| Vec::new());
             // Increment the sold_ticket_count and save the participant's address
             for i in 0..real_purchase_ticket_count{
-                TICKET_STATUS.save(deps.storage, (game_id.clone(), start_ticket_number.clone() + i as u64) , &info.sender.clone())?;
-                tickets.push(start_ticket_number.clone() + 1 + i as u64);
+                TICKET_STATUS.save(deps.storage, (game_id, start_ticket_number + i as u64) , &buyer.clone())?;
+                tickets.push(start_ticket_number + 1 + i as u64);
             }
             // Save the updated list back to storage
-            WALLET_TICKETS.save(deps.storage, key, &tickets)?;
-            game_state.sold_ticket_count += real_purchase_ticket_count.clone() as u64;
+            WALLET_TICKETS.save(deps.storage, key.clone(), &tickets)?;
+
+            let paid_amount = ticket_price * real_purchase_ticket_count;
+            let mut payments = WALLET_PAYMENTS.may_load(deps.storage, key.clone())?.unwrap_or_default();
+            add_asset_amount(&mut payments, &asset, paid_amount);
+            WALLET_PAYMENTS.save(deps.storage, key, &payments)?;
+            let mut proceeds = GAME_PROCEEDS.may_load(deps.storage, game_id)?.unwrap_or_default();
+            add_asset_amount(&mut proceeds, &asset, paid_amount);
+            GAME_PROCEEDS.save(deps.storage, game_id, &proceeds)?;
+            add_liability(deps.storage, &asset, paid_amount)?;
+
+            game_state.sold_ticket_count += real_purchase_ticket_count as u64;
+            game_state.entropy = mix_purchase_entropy(&game_state.entropy, &buyer, start_ticket_number, real_purchase_ticket_count as u64, &env);
             GAME_STATE.save(deps.storage, game_id , &game_state)?;
 
-            let refund_amount = sent_funds.clone() - ticket_price * real_purchase_ticket_count.clone();
+            let refund_amount = sent_funds - paid_amount;
 
             if refund_amount > 0 {
-                let send_msg = BankMsg::Send {
-                    to_address: info.sender.into_string(),
-                    amount: vec![coin(refund_amount, "usei")]
-                };
+                let send_msg = send_asset_msg(&asset, &buyer, refund_amount)?;
                 Ok(Response::new().add_attribute("action", "enter_raffle")
                     .add_attribute("start_ticket_number", (start_ticket_number + 1).to_string())
                     .add_attribute("purchase_ticket_count", real_purchase_ticket_count.to_string())
@@ -223,31 +866,34 @@ fn try_enter_raffle(
             }
         },
         Err(_) => {
-            return Err(ContractError::WrongGameId {});
+            Err(ContractError::WrongGameId {})
         }
     }
 }
 
 fn try_transfer_tokens_to_collection_wallet(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     amount: u128, // Amount of tokens to transfer
-    denom: String, // Token denomination, e.g., "usei" for micro SEI tokens
+    asset: AssetInfo, // Native denom or CW20 token to transfer
     collection_wallet_address: String, // Address of the collection wallet
 ) -> Result<Response, ContractError> {
-    let global_state = GLOBAL_STATE.load(deps.storage)?;
     let collection_wallet = collection_wallet_address.clone();
-    // Authorization check: Ensure the caller is the owner
-    if info.sender != global_state.owner {
+    // Authorization check: Ensure the caller is a treasurer
+    if !has_role(deps.storage, &info.sender, Role::Treasurer)? {
         return Err(ContractError::Unauthorized {  });
     }
 
+    // Only what isn't owed to a raffle can be withdrawn
+    let liability = asset_amount(&LIABILITIES.may_load(deps.storage)?.unwrap_or_default(), &asset);
+    let balance = asset_balance(&deps.querier, &env.contract.address, &asset)?;
+    if Uint128::from(amount) > balance.saturating_sub(liability) {
+        return Err(ContractError::InsufficientFreeBalance {});
+    }
+
     // Create the message to transfer tokens
-    let send_msg = BankMsg::Send {
-        to_address: collection_wallet_address,
-        amount: vec![coin(amount, denom)],
-    };
+    let send_msg = send_asset_msg(&asset, &deps.api.addr_validate(&collection_wallet_address)?, amount)?;
 
     // Create and return the response that sends the tokens
     Ok(Response::new()
@@ -257,99 +903,690 @@ fn try_transfer_tokens_to_collection_wallet(
         .add_attribute("to", collection_wallet))
 }
 
+fn try_update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    update: ConfigUpdate,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if config.admin.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(default_denom) = update.default_denom {
+        config.default_denom = default_denom;
+    }
+    if let Some(platform_fee_bps) = update.platform_fee_bps {
+        config.platform_fee_bps = platform_fee_bps;
+    }
+    if let Some(fee_recipient) = update.fee_recipient {
+        config.fee_recipient = deps.api.addr_validate(&fee_recipient)?;
+    }
+    if let Some(creation_enabled) = update.creation_enabled {
+        config.creation_enabled = creation_enabled;
+    }
+    if let Some(creation_fee) = update.creation_fee {
+        config.creation_fee = Some(creation_fee).filter(|fee| !fee.amount.is_zero());
+    }
+    if let Some(min_duration) = update.min_duration {
+        config.min_duration = min_duration;
+    }
+    if let Some(max_duration) = update.max_duration {
+        config.max_duration = max_duration;
+    }
+    if let Some(drand) = update.drand {
+        config.drand = Some(drand).filter(|drand| !drand.public_key.is_empty());
+    }
+    if let Some(randomness_proxy) = update.randomness_proxy {
+        config.randomness_proxy = if randomness_proxy.is_empty() {
+            None
+        } else {
+            Some(deps.api.addr_validate(&randomness_proxy)?)
+        };
+    }
+    validate_config(&config)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "update_config"))
+}
+
+fn try_propose_new_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    expires_at: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.admin.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if expires_at.is_some_and(|expires_at| expires_at <= env.block.time.seconds() * 1000) {
+        return Err(ContractError::OwnershipProposalExpired {});
+    }
+
+    // Replaces any earlier proposal
+    let owner = deps.api.addr_validate(&owner)?;
+    PENDING_OWNER.save(deps.storage, &PendingOwner { owner: owner.clone(), expires_at })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_new_owner")
+        .add_attribute("pending_owner", owner.into_string()))
+}
+
+fn try_accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending_owner = PENDING_OWNER.may_load(deps.storage)?.ok_or(ContractError::NoPendingOwner {})?;
+    if info.sender != pending_owner.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if pending_owner.expires_at.is_some_and(|expires_at| expires_at <= env.block.time.seconds() * 1000) {
+        return Err(ContractError::OwnershipProposalExpired {});
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.admin = Some(pending_owner.owner.clone());
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("admin", pending_owner.owner.into_string()))
+}
+
+fn try_renounce_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if config.admin.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Nobody could lift a pause afterwards, which would lock the prizes and ticket payments of the paused games
+    if GLOBAL_STATE.load(deps.storage)?.paused_since.is_some() {
+        return Err(ContractError::PausedAtRenounce {});
+    }
+    for item in GAME_STATE.range(deps.storage, None, None, cosmwasm_std::Order::Ascending) {
+        if item?.1.paused_since.is_some() {
+            return Err(ContractError::PausedAtRenounce {});
+        }
+    }
+
+    // Every admin check fails from now on, and nobody keeps powers the admin delegated
+    config.admin = None;
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_OWNER.remove(deps.storage);
+    let holders = ROLES.keys(deps.storage, None, None, cosmwasm_std::Order::Ascending).collect::<StdResult<Vec<Addr>>>()?;
+    for holder in holders {
+        ROLES.remove(deps.storage, holder);
+    }
+
+    Ok(Response::new().add_attribute("action", "renounce_ownership"))
+}
+
+// Either the game itself or the whole contract is paused
+fn is_paused(storage: &dyn Storage, game_state: &GameState) -> StdResult<bool> {
+    Ok(game_state.paused_since.is_some() || GLOBAL_STATE.load(storage)?.paused_since.is_some())
+}
+
+// Total time the contract was paused, the current pause included
+fn global_paused_time(global_state: &GlobalState, now: u64) -> u64 {
+    global_state.paused_time + global_state.paused_since.map_or(0, |since| now.saturating_sub(since))
+}
+
+// Time the game was paused by itself or through the global switch, overlapping pauses count twice
+fn game_paused_time(storage: &dyn Storage, game_state: &GameState, now: u64) -> StdResult<u64> {
+    let own = game_state.paused_time + game_state.paused_since.map_or(0, |since| now.saturating_sub(since));
+    let global = global_paused_time(&GLOBAL_STATE.load(storage)?, now).saturating_sub(game_state.global_paused_offset);
+    Ok(own + global)
+}
+
+// Starts or ends a pause, pausing twice keeps the first start
+fn set_paused(paused_since: &mut Option<u64>, paused_time: &mut u64, paused: bool, now: u64) {
+    match (*paused_since, paused) {
+        (None, true) => *paused_since = Some(now),
+        (Some(since), false) => {
+            *paused_time += now.saturating_sub(since);
+            *paused_since = None;
+        },
+        _ => {},
+    }
+}
+
+// Pauses every game, or a single one when game_id is given
+fn try_set_paused(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    game_id: Option<u64>,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds() * 1000;
+    let response = Response::new()
+        .add_attribute("action", if paused { "pause" } else { "unpause" });
+
+    match game_id {
+        Some(game_id) => {
+            if !has_role(deps.storage, &info.sender, Role::Operator)? {
+                return Err(ContractError::Unauthorized {});
+            }
+            let mut game_state = GAME_STATE.load(deps.storage, game_id)
+                .map_err(|_| ContractError::WrongGameId {})?;
+            set_paused(&mut game_state.paused_since, &mut game_state.paused_time, paused, now);
+            GAME_STATE.save(deps.storage, game_id, &game_state)?;
+            Ok(response.add_attribute("game_id", game_id.to_string()))
+        },
+        None => {
+            if !has_role(deps.storage, &info.sender, Role::Admin)? {
+                return Err(ContractError::Unauthorized {});
+            }
+            let mut global_state = GLOBAL_STATE.load(deps.storage)?;
+            set_paused(&mut global_state.paused_since, &mut global_state.paused_time, paused, now);
+            GLOBAL_STATE.save(deps.storage, &global_state)?;
+            Ok(response)
+        },
+    }
+}
+
+// The config admin holds every role, and so does anyone granted Role::Admin
+fn has_role(storage: &dyn Storage, address: &Addr, role: Role) -> StdResult<bool> {
+    if CONFIG.load(storage)?.admin.as_ref() == Some(address) {
+        return Ok(true);
+    }
+    let roles = ROLES.may_load(storage, address.clone())?.unwrap_or_default();
+    Ok(roles.contains(&role) || roles.contains(&Role::Admin))
+}
+
+fn try_update_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+    role: Role,
+    granted: bool,
+) -> Result<Response, ContractError> {
+    if !has_role(deps.storage, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let address = deps.api.addr_validate(&address)?;
+    let mut roles = ROLES.may_load(deps.storage, address.clone())?.unwrap_or_default();
+    roles.retain(|existing| *existing != role);
+    if granted {
+        roles.push(role);
+    }
+    if roles.is_empty() {
+        ROLES.remove(deps.storage, address.clone());
+    } else {
+        ROLES.save(deps.storage, address.clone(), &roles)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", if granted { "grant_role" } else { "revoke_role" })
+        .add_attribute("address", address.into_string()))
+}
+
+fn try_update_allowed_collection(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract_addr: String,
+    allowed: bool,
+) -> Result<Response, ContractError> {
+    if !has_role(deps.storage, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let collection = deps.api.addr_validate(&contract_addr)?;
+    if allowed {
+        ALLOWED_COLLECTIONS.save(deps.storage, collection, &true)?;
+    } else {
+        ALLOWED_COLLECTIONS.remove(deps.storage, collection);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", if allowed { "add_allowed_collection" } else { "remove_allowed_collection" })
+        .add_attribute("contract_addr", contract_addr))
+}
+
+fn try_update_allowed_token(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract_addr: String,
+    allowed: bool,
+) -> Result<Response, ContractError> {
+    if !has_role(deps.storage, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let token = deps.api.addr_validate(&contract_addr)?;
+    if allowed {
+        ALLOWED_TOKENS.save(deps.storage, token, &true)?;
+    } else {
+        ALLOWED_TOKENS.remove(deps.storage, token);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", if allowed { "add_allowed_token" } else { "remove_allowed_token" })
+        .add_attribute("contract_addr", contract_addr))
+}
+
+fn try_deposit_creation_credit(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let sent = match info.funds.as_slice() {
+        [sent] => sent,
+        _ => return Err(ContractError::IncorrectFunds {}),
+    };
+
+    let key = (info.sender.clone(), sent.denom.clone());
+    let credit = CREATION_CREDITS.may_load(deps.storage, key.clone())?.unwrap_or_default() + sent.amount;
+    CREATION_CREDITS.save(deps.storage, key, &credit)?;
+    add_liability(deps.storage, &AssetInfo::Native { denom: sent.denom.clone() }, sent.amount.u128())?;
+
+    Ok(Response::new()
+        .add_attribute("action", "deposit_creation_credit")
+        .add_attribute("wallet", info.sender.into_string())
+        .add_attribute("credit", format!("{}{}", credit, sent.denom)))
+}
+
+fn try_withdraw_creation_credit(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    let key = (info.sender.clone(), denom.clone());
+    let credit = CREATION_CREDITS.may_load(deps.storage, key.clone())?.unwrap_or_default();
+    if credit.is_zero() {
+        return Err(ContractError::InsufficientCreationCredit {});
+    }
+    CREATION_CREDITS.remove(deps.storage, key);
+    let asset = AssetInfo::Native { denom };
+    release_liability(deps.storage, &asset, credit.u128())?;
+
+    Ok(Response::new()
+        .add_message(send_asset_msg(&asset, &info.sender, credit.u128())?)
+        .add_attribute("action", "withdraw_creation_credit")
+        .add_attribute("amount", credit.to_string()))
+}
+
 fn try_select_winner_and_transfer_nft_to_winner(
     deps: DepsMut,
     env: Env,
-    _info: MessageInfo,
-    game_id: u64
+    info: MessageInfo,
+    game_id: u64,
+    secret: Option<String>,
+    beacon: Option<DrandBeacon>,
 ) -> Result<Response, ContractError> {
-    match GAME_STATE.load(deps.storage, game_id.clone()) {
+    match GAME_STATE.load(deps.storage, game_id) {
         Ok(mut game_state) => {
-            if game_state.raffle_status.clone() == 0 {
+            if game_state.raffle_status == RAFFLE_DRAWING {
+                // Randomness the proxy delivered during a pause is drawn by anyone once the game is unpaused
+                let callback = PENDING_RANDOMNESS.may_load(deps.storage, game_id)?.ok_or(ContractError::RandomnessPending {})?;
+                if is_paused(deps.storage, &game_state)? {
+                    return Err(ContractError::Paused {});
+                }
+                PENDING_RANDOMNESS.remove(deps.storage, game_id);
+                return draw_winner(deps, env, game_id, game_state, callback.randomness.clone(), None, Some(callback));
+            }
+            if game_state.raffle_status != RAFFLE_ACTIVE {
                 return Err(ContractError::RaffleEnded {});
             }
             if game_state.end_time > env.block.time.seconds() * 1000 {
                 return Err(ContractError::CantFinishGame {});
             }
+            if is_paused(deps.storage, &game_state)? {
+                return Err(ContractError::Paused {});
+            }
+            // Nobody is stuck waiting for a keeper that never shows up
+            if info.sender != game_state.owner && !has_role(deps.storage, &info.sender, Role::Keeper)?
+                && game_state.end_time + KEEPER_WINDOW > env.block.time.seconds() * 1000 {
+                return Err(ContractError::Unauthorized {});
+            }
 
-            let mod_number = game_state.total_ticket_count as u64;
-            let sold_count = game_state.sold_ticket_count as u64;
-            let seed_assist = sold_count % mod_number.clone() * (env.block.time.nanos() / 1024 / mod_number.clone() + env.block.height.clone() % mod_number.clone() * 256 % mod_number.clone() + 1) % mod_number.clone();
-            let seed = (env.block.time.nanos() % mod_number + env.block.height + seed_assist) % mod_number;
-            let winner_index = seed % mod_number;
+            // Without any buyer there is nobody to draw, the NFT goes back to its owner
+            if game_state.sold_ticket_count == 0 {
+                game_state.raffle_status = RAFFLE_NO_SALES;
+                GAME_STATE.save(deps.storage, game_id, &game_state)?;
 
-            
+                return Ok(Response::new()
+                    .add_messages(return_prizes_msgs(deps.storage, &game_state)?)
+                    .add_attribute("action", "select_winner")
+                    .add_attribute("game_id", game_id.to_string())
+                    .add_attribute("status", "No tickets were sold"));
+            }
+
+            if !minimum_sales_reached(&game_state) {
+                game_state.raffle_status = RAFFLE_REFUNDING;
+                GAME_STATE.save(deps.storage, game_id, &game_state)?;
+
+                return Ok(Response::new()
+                    .add_messages(return_prizes_msgs(deps.storage, &game_state)?)
+                    .add_attribute("action", "select_winner")
+                    .add_attribute("game_id", game_id.to_string())
+                    .add_attribute("status", "Minimum sales not reached, refunding"));
+            }
 
-            // Check if the winner's ticket was actually sold
-            match TICKET_STATUS.load(deps.storage, (game_id.clone(), winner_index.clone() as u64)) {
-                Ok(winner_ticket) => {
+            // Nobody revealed the secret in time, so the raffle can't be drawn anymore
+            if reveal_expired(deps.storage, &game_state, &env)? {
+                game_state.raffle_status = RAFFLE_REFUNDING;
+                GAME_STATE.save(deps.storage, game_id, &game_state)?;
+
+                return Ok(Response::new()
+                    .add_messages(return_prizes_msgs(deps.storage, &game_state)?)
+                    .add_attribute("action", "select_winner")
+                    .add_attribute("game_id", game_id.to_string())
+                    .add_attribute("status", "Secret was not revealed, refunding"));
+            }
+
+            let (randomness, beacon) = match game_state.draw_mode {
+                DrawMode::CommitReveal => {
+                    let secret = secret.ok_or(ContractError::MissingSecret {})?;
+                    if sha256_hex(secret.as_bytes()) != game_state.secret_hash {
+                        return Err(ContractError::InvalidSecret {});
+                    }
+                    (secret, None)
+                },
+                DrawMode::Drand => {
+                    let beacon = beacon.ok_or(ContractError::MissingBeacon {})?;
+                    let drand = game_state.drand.clone().ok_or(ContractError::DrandNotConfigured {})?;
 
-                    let transfer_msg = Cw721ExecuteMsg::TransferNft {
-                        recipient: winner_ticket.clone().into_string(),
-                        token_id: game_state.nft_token_id.clone(),
+                    // Only the first round after end_time is accepted, so the caller can't shop for a favourable round
+                    let expected = first_round_after(game_state.end_time, drand.genesis_time, drand.period);
+                    if beacon.round != expected {
+                        return Err(ContractError::WrongDrandRound { expected });
+                    }
+                    if !verify_beacon(drand.public_key.as_slice(), beacon.round, beacon.previous_signature.as_slice(), beacon.signature.as_slice()) {
+                        return Err(ContractError::InvalidBeacon {});
+                    }
+                    let randomness = hex::encode(beacon_randomness(beacon.signature.as_slice()));
+                    (randomness, Some(DrawBeacon {
+                        public_key: drand.public_key,
+                        round: beacon.round,
+                        previous_signature: beacon.previous_signature,
+                        signature: beacon.signature,
+                    }))
+                },
+                DrawMode::Proxy => {
+                    let proxy = game_state.randomness_proxy.clone().ok_or(ContractError::ProxyNotConfigured {})?;
+
+                    // The winner is only picked in the callback, so the caller can't retry until they like the outcome
+                    game_state.raffle_status = RAFFLE_DRAWING;
+                    game_state.randomness_requested_at = Some(env.block.time.seconds() * 1000);
+                    GAME_STATE.save(deps.storage, game_id, &game_state)?;
+
+                    let msg = WasmMsg::Execute {
+                        contract_addr: proxy.into_string(),
+                        msg: to_json_binary(&ProxyExecuteMsg::GetNextRandomness { job_id: game_id.to_string() })?,
+                        funds: info.funds, // Fee charged by the proxy
                     };
-        
-                    let msg = CosmosMsg::Wasm(WasmMsg::Execute {
-                        contract_addr: game_state.nft_contract_addr.clone().into_string(),
-                        msg: to_json_binary(&transfer_msg)?,
-                        funds: vec![],
-                    });
-        
-                    // Update the state before returning the response
-                    game_state.raffle_status = 0; // End the raffle by setting the status to 0
-                    GAME_STATE.save(deps.storage, game_id.clone(), &game_state)?;
-        
-                    // Return a response with the winner information and the transfer message
-                    Ok(Response::new()
+                    return Ok(Response::new()
                         .add_message(msg)
-                        .add_attribute("action", "select_winner_and_transfer_nft")
-                        .add_attribute("game_id", game_id.to_string())
-                        .add_attribute("winner_ticket", (winner_index + 1).to_string())
-                        .add_attribute("winner", winner_ticket.into_string())
-                        .add_attribute("nft_contract_addr", game_state.nft_contract_addr.into_string())
-                        .add_attribute("token_id", game_state.nft_token_id))                    
+                        .add_attribute("action", "request_randomness")
+                        .add_attribute("game_id", game_id.to_string()));
                 },
-                Err(_) => {
-                    // If the ticket wasn't sold, simply end the raffle with transferring the NFT to collection wallet.
-                    let transfer_msg = Cw721ExecuteMsg::TransferNft {
-                        recipient: game_state.collection_wallet.clone().into_string(),
-                        token_id: game_state.nft_token_id.clone(),
-                    };
-        
-                    let msg = CosmosMsg::Wasm(WasmMsg::Execute {
-                        contract_addr: game_state.nft_contract_addr.clone().into_string(),
-                        msg: to_json_binary(&transfer_msg)?,
-                        funds: vec![],
-                    });
+            };
 
-                    game_state.raffle_status = 0; // End the raffle
-                    GAME_STATE.save(deps.storage, game_id.clone(), &game_state)?;
+            draw_winner(deps, env, game_id, game_state, randomness, beacon, None)
+        },
+        Err(_) => {
+            Err(ContractError::WrongGameId {})
+        }
+    }
+    
+}
 
-                    Ok(Response::new()
-                        .add_message(msg)
-                        .add_attribute("action", "select_winner")
-                        .add_attribute("game_id", game_id.to_string())
-                        .add_attribute("winner_ticket", (winner_index + 1).to_string())
-                        .add_attribute("status", "Winner ticket was not sold"))
+// Picks a winning ticket for every placement from the external randomness and hands out the prizes
+fn draw_winner(
+    deps: DepsMut,
+    env: Env,
+    game_id: u64,
+    mut game_state: GameState,
+    randomness: String,
+    beacon: Option<DrawBeacon>,
+    callback: Option<DrawCallback>,
+) -> Result<Response, ContractError> {
+    let winner_indexes = select_winner_indexes(&randomness, &game_state.entropy, game_state.sold_ticket_count, game_state.prizes.len() as u64);
+
+    let mut response = Response::new()
+        .add_attribute("action", "select_winner_and_transfer_nft")
+        .add_attribute("game_id", game_id.to_string());
+    // Pot shares are computed on the whole pot, so two 50% shares split it evenly
+    let pot = GAME_PROCEEDS.may_load(deps.storage, game_id)?.unwrap_or_default();
+    let mut proceeds = pot.clone();
+    let mut winners: Vec<Addr> = Vec::new();
+    for (index, prize) in game_state.prizes.iter_mut().enumerate() {
+        match winner_indexes.get(index) {
+            Some(winner_index) => {
+                let winner = TICKET_STATUS.load(deps.storage, (game_id, *winner_index))?;
+                let place = index + 1;
+                for (item_index, item) in prize.items.iter_mut().enumerate() {
+                    match item {
+                        PrizeItem::PotShare { bps } => {
+                            for total in pot.iter() {
+                                let share = total.amount.multiply_ratio(*bps, 10000u128).u128();
+                                if share > 0 {
+                                    sub_asset_amount(&mut proceeds, &total.asset, share)?;
+                                    release_liability(deps.storage, &total.asset, share)?;
+                                    response = response.add_message(send_asset_msg(&total.asset, &winner, share)?);
+                                }
+                            }
+                        },
+                        // Same randomness as the winning tickets, so the box content is as unpredictable as the winner
+                        PrizeItem::MysteryBox { pool } => {
+                            let seed = Sha256::digest(format!("{}{}{}/{}", randomness, game_state.entropy, place, item_index).as_bytes());
+                            *item = take_vault_nft(deps.storage, &pool.clone(), &seed)?;
+                        },
+                        _ => {},
+                    }
                 }
+                response = response
+                    .add_messages(escrowed_prize_msgs(deps.storage, prize, &winner)?)
+                    .add_attribute(format!("place_{}_ticket", place), (winner_index + 1).to_string())
+                    .add_attribute(format!("place_{}_winner", place), winner.to_string())
+                    .add_attribute(format!("place_{}_prize", place), prize_label(prize));
+                winners.push(winner);
+            },
+            // Fewer tickets were sold than there are prizes, an unawarded pot share stays in the proceeds
+            None => {
+                release_mystery_boxes(deps.storage, prize)?;
+                response = response.add_messages(escrowed_prize_msgs(deps.storage, prize, &game_state.owner)?);
+            },
+        }
+    }
+    // The rest of the ticket sales is paid out right away, nobody has to move it by hand
+    let config = CONFIG.load(deps.storage)?;
+    let mut payouts: Vec<AssetAmount> = Vec::new();
+    for remaining in proceeds.iter_mut() {
+        if !remaining.amount.is_zero() {
+            let sale_price = asset_amount(&pot, &remaining.asset);
+            response = response.add_messages(payout_msgs(deps.as_ref(), &config.fee_recipient, &game_state, &remaining.asset, remaining.amount.u128(), sale_price)?);
+            release_liability(deps.storage, &remaining.asset, remaining.amount.u128())?;
+            payouts.push(remaining.clone());
+            remaining.amount = Uint128::zero();
+        }
+    }
+    GAME_PROCEEDS.save(deps.storage, game_id, &proceeds)?;
+    GAME_PAYOUTS.save(deps.storage, game_id, &payouts)?;
+
+    DRAW_RECORDS.save(deps.storage, game_id, &DrawRecord {
+        block_height: env.block.height,
+        block_time: env.block.time.nanos(),
+        sold_ticket_count: game_state.sold_ticket_count,
+        entropy: game_state.entropy.clone(),
+        randomness,
+        beacon,
+        callback,
+        winner_indexes,
+        winners,
+    })?;
+
+    game_state.raffle_status = RAFFLE_ENDED; // End the raffle
+    GAME_STATE.save(deps.storage, game_id, &game_state)?;
+
+    Ok(response)
+}
+
+fn try_receive_randomness(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    callback: NoisCallback,
+) -> Result<Response, ContractError> {
+    let game_id: u64 = callback.job_id.parse().map_err(|_| ContractError::WrongGameId {})?;
+    let game_state = GAME_STATE.load(deps.storage, game_id)
+        .map_err(|_| ContractError::WrongGameId {})?;
+    // Only the proxy the game was started with, changing the config doesn't hand the draw to another contract
+    if game_state.randomness_proxy.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if game_state.raffle_status != RAFFLE_DRAWING {
+        return Err(ContractError::RandomnessNotRequested {});
+    }
+    let callback = DrawCallback {
+        job_id: callback.job_id,
+        published: callback.published,
+        randomness: callback.randomness.to_hex(),
+    };
+    // The proxy delivers a job only once, so randomness arriving during a pause is kept for later
+    if is_paused(deps.storage, &game_state)? {
+        PENDING_RANDOMNESS.save(deps.storage, game_id, &callback)?;
+        return Ok(Response::new()
+            .add_attribute("action", "hold_randomness")
+            .add_attribute("game_id", game_id.to_string()));
+    }
+
+    draw_winner(deps, env, game_id, game_state, callback.randomness.clone(), None, Some(callback))
+}
+
+fn try_cancel_raffle(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    game_id: u64,
+) -> Result<Response, ContractError> {
+    let mut game_state = GAME_STATE.load(deps.storage, game_id)
+        .map_err(|_| ContractError::WrongGameId {})?;
+
+    // A raffle waiting for the proxy can only be unwound once the callback is overdue. Randomness held during
+    // a pause is readable by anyone, so the outcome is already known and cancelling would veto the draw.
+    match game_state.raffle_status {
+        RAFFLE_ACTIVE => {},
+        RAFFLE_DRAWING => {
+            let requested_at = game_state.randomness_requested_at.unwrap_or_default();
+            if PENDING_RANDOMNESS.has(deps.storage, game_id) || requested_at.saturating_add(RANDOMNESS_TIMEOUT) > env.block.time.seconds() * 1000 {
+                return Err(ContractError::CantCancelRaffle {});
             }
         },
-        Err(_) => {
-            return Err(ContractError::WrongGameId {});
+        _ => return Err(ContractError::CantCancelRaffle {}),
+    }
+    // Once tickets are sold the creator may already know the outcome (their own secret, published
+    // randomness), so only operators can call the raffle off from then on
+    if !has_role(deps.storage, &info.sender, Role::Operator)? {
+        if info.sender != game_state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        if game_state.raffle_status != RAFFLE_ACTIVE || game_state.sold_ticket_count > 0 {
+            return Err(ContractError::CantCancelRaffle {});
         }
     }
-    
+
+    game_state.raffle_status = RAFFLE_CANCELLED;
+    GAME_STATE.save(deps.storage, game_id, &game_state)?;
+
+    Ok(Response::new()
+        .add_messages(return_prizes_msgs(deps.storage, &game_state)?)
+        .add_attribute("action", "cancel_raffle")
+        .add_attribute("game_id", game_id.to_string())
+        .add_attribute("sold_ticket_count", game_state.sold_ticket_count.to_string()))
 }
 
+fn try_claim_refund(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    game_id: u64,
+) -> Result<Response, ContractError> {
+    let mut game_state = GAME_STATE.load(deps.storage, game_id)
+        .map_err(|_| ContractError::WrongGameId {})?;
+
+    // Buyers don't have to wait for someone to finalize a raffle whose secret was never revealed
+    let mut response = Response::new();
+    if game_state.raffle_status == RAFFLE_ACTIVE && reveal_expired(deps.storage, &game_state, &env)? {
+        game_state.raffle_status = RAFFLE_REFUNDING;
+        GAME_STATE.save(deps.storage, game_id, &game_state)?;
+        response = response.add_messages(return_prizes_msgs(deps.storage, &game_state)?);
+    }
+    if game_state.raffle_status != RAFFLE_REFUNDING && game_state.raffle_status != RAFFLE_CANCELLED {
+        return Err(ContractError::RaffleNotRefunding {});
+    }
+
+    let key = (game_id, info.sender.clone());
+    if REFUND_CLAIMED.may_load(deps.storage, key.clone())?.unwrap_or(false) {
+        return Err(ContractError::RefundAlreadyClaimed {});
+    }
+    // Exactly what was paid, in every asset it was paid with
+    let payments = WALLET_PAYMENTS.may_load(deps.storage, key.clone())?.unwrap_or_default();
+    if payments.is_empty() {
+        return Err(ContractError::NoTicketsToRefund {});
+    }
+    REFUND_CLAIMED.save(deps.storage, key, &true)?;
+
+    let mut proceeds = GAME_PROCEEDS.may_load(deps.storage, game_id)?.unwrap_or_default();
+    for payment in payments.iter() {
+        sub_asset_amount(&mut proceeds, &payment.asset, payment.amount.u128())?;
+        release_liability(deps.storage, &payment.asset, payment.amount.u128())?;
+        response = response.add_message(send_asset_msg(&payment.asset, &info.sender, payment.amount.u128())?);
+    }
+    GAME_PROCEEDS.save(deps.storage, game_id, &proceeds)?;
+
+    Ok(response
+        .add_attribute("action", "claim_refund")
+        .add_attribute("game_id", game_id.to_string())
+        .add_attribute("wallet", info.sender.into_string()))
+}
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetGlobalInfo {} => to_json_binary(&query_global_info(deps)?),
-        QueryMsg::GetGameInfo { game_id } => to_json_binary(&query_game_info(deps, game_id)?),
+        QueryMsg::GetConfig {} => to_json_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::GetRoles { start_after, limit } => to_json_binary(&query_roles(deps, start_after, limit)?),
+        QueryMsg::GetPendingOwner {} => {
+            let pending_owner = PENDING_OWNER.may_load(deps.storage)?;
+            to_json_binary(&PendingOwnerResponse {
+                expires_at: pending_owner.as_ref().and_then(|pending_owner| pending_owner.expires_at),
+                pending_owner: pending_owner.map(|pending_owner| pending_owner.owner),
+            })
+        },
+        QueryMsg::GetGameInfo { game_id } => to_json_binary(&query_game_info(deps, env, game_id)?),
         QueryMsg::GetTicketsForWallet { game_id, wallet_addr } => to_json_binary(&query_tickets_for_wallet(deps, game_id, wallet_addr)?),
         QueryMsg::GetAllGames {} => to_json_binary(&query_all_games(deps)?),
-        QueryMsg::GetBalance {} => to_json_binary(&query_sei_balance(deps, env)?),
+        QueryMsg::GetBalance { denom } => {
+            let denom = match denom {
+                Some(denom) => denom,
+                None => CONFIG.load(deps.storage)?.default_denom,
+            };
+            to_json_binary(&query_balance(deps, env, denom)?)
+        },
+        QueryMsg::VerifyDraw { game_id } => to_json_binary(&query_verify_draw(deps, game_id)?),
+        QueryMsg::GetVault {} => to_json_binary(&query_vault(deps)?),
+        QueryMsg::GetTreasury {} => to_json_binary(&query_treasury(deps, env)?),
+        QueryMsg::GetAllowedCollections { start_after, limit } => to_json_binary(&query_allowed_collections(deps, start_after, limit)?),
+        QueryMsg::GetAllowedTokens { start_after, limit } => to_json_binary(&query_allowed_tokens(deps, start_after, limit)?),
+        QueryMsg::GetCreationCredit { wallet, denom } => to_json_binary(&CreationCreditResponse {
+            credit: CREATION_CREDITS.may_load(deps.storage, (wallet, denom))?.unwrap_or_default(),
+        }),
     }
 }
 
@@ -358,24 +1595,39 @@ fn query_global_info(deps: Deps) -> StdResult<GlobalResponse> {
 
     Ok(GlobalResponse { 
         raffle_count: global_state.count,
-        owner: global_state.owner
+        owner: CONFIG.load(deps.storage)?.admin,
+        paused: global_state.paused_since.is_some(),
     })
 }
 
-fn query_game_info(deps: Deps, game_id: u64) -> StdResult<GameResponse> {
+fn query_game_info(deps: Deps, env: Env, game_id: u64) -> StdResult<GameResponse> {
     let game_state = GAME_STATE.load(deps.storage, game_id)
         .map_err(|_| StdError::generic_err("Game with provided ID does not exist"))?;
+    let paused = is_paused(deps.storage, &game_state)?;
 
     Ok(GameResponse { 
-        ticket_price: game_state.ticket_price,
+        prices: game_state.prices,
+        proceeds: GAME_PROCEEDS.may_load(deps.storage, game_id)?.unwrap_or_default(),
+        paid_out: GAME_PAYOUTS.may_load(deps.storage, game_id)?.unwrap_or_default(),
         sold_ticket_count: game_state.sold_ticket_count,
         total_ticket_count: game_state.total_ticket_count,
         raffle_status: game_state.raffle_status,
-        nft_contract_addr: game_state.nft_contract_addr,
-        nft_token_id: game_state.nft_token_id,
+        status: get_game_status(game_state.raffle_status, game_state.end_time, env.block.time.seconds(), paused)?,
+        paused,
+        prizes: game_state.prizes,
+        winners: DRAW_RECORDS.may_load(deps.storage, game_id)?.map_or(vec![], |record| record.winners),
         owner: game_state.owner,
         collection_wallet: game_state.collection_wallet,
+        payout_split: game_state.payout_split,
+        platform_fee_bps: game_state.platform_fee_bps,
         end_time: game_state.end_time,
+        min_tickets_sold: game_state.min_tickets_sold,
+        reserve_amount: game_state.reserve_amount,
+        draw_mode: game_state.draw_mode,
+        drand: game_state.drand,
+        randomness_proxy: game_state.randomness_proxy,
+        secret_hash: game_state.secret_hash,
+        entropy: game_state.entropy,
     })
 }
 
@@ -390,7 +1642,7 @@ fn query_tickets_for_wallet(
     let tickets = WALLET_TICKETS.load(deps.storage, key).unwrap_or_else(|_| Vec::new());
 
     Ok(WalletTicketResponse{
-        tickets: tickets
+        tickets
     })
 }
 
@@ -405,20 +1657,131 @@ pub fn query_all_games(deps: Deps) -> StdResult<AllGamesResponse> {
     })
 }
 
-pub fn query_sei_balance(deps: Deps, env: Env) -> StdResult<BalanceResponse> {
-    let sei_denom = "usei";
-
+pub fn query_balance(deps: Deps, env: Env, denom: String) -> StdResult<BalanceResponse> {
     let query = QueryRequest::Bank(BankQuery::Balance {
         address: env.contract.address.to_string(),
-        denom: sei_denom.to_string(),
+        denom,
     });
 
     // Execute the query
     let res: cosmwasm_std::BalanceResponse = deps.querier.query(&query)?;
 
-    // Find the SEI token in the response
-    let sei_balance = res.amount; // Assuming `amount` is the field you're interested in.
-
     // Construct your BalanceResponse, assuming it expects a Coin
-    Ok(BalanceResponse { balance: sei_balance })
-}
\ No newline at end of file
+    Ok(BalanceResponse { balance: res.amount })
+}
+
+// Replays the winner selection of a game from its recorded inputs
+pub fn query_verify_draw(deps: Deps, game_id: u64) -> StdResult<VerifyDrawResponse> {
+    let record = DRAW_RECORDS.load(deps.storage, game_id)
+        .map_err(|_| StdError::generic_err("No draw recorded for the provided game ID"))?;
+
+    let game_state = GAME_STATE.load(deps.storage, game_id)?;
+
+    let winner_indexes = select_winner_indexes(&record.randomness, &record.entropy, record.sold_ticket_count, game_state.prizes.len() as u64);
+    let winners = winner_indexes.iter()
+        .map(|winner_index| TICKET_STATUS.load(deps.storage, (game_id, *winner_index)))
+        .collect::<StdResult<Vec<Addr>>>()?;
+    let randomness_valid = match game_state.draw_mode {
+        // The revealed secret must match the commitment made at creation
+        DrawMode::CommitReveal => sha256_hex(record.randomness.as_bytes()) == game_state.secret_hash,
+        // The beacon must be the round the game was bound to, signed by the network it was started with
+        DrawMode::Drand => match (&record.beacon, &game_state.drand) {
+            (Some(beacon), Some(drand)) => beacon.public_key == drand.public_key
+                && beacon.round == first_round_after(game_state.end_time, drand.genesis_time, drand.period)
+                && verify_beacon(beacon.public_key.as_slice(), beacon.round, beacon.previous_signature.as_slice(), beacon.signature.as_slice())
+                && hex::encode(beacon_randomness(beacon.signature.as_slice())) == record.randomness,
+            _ => false,
+        },
+        // Only the proxy could deliver the callback, what can be checked is that it answered this game's job
+        DrawMode::Proxy => record.callback.as_ref()
+            .is_some_and(|callback| callback.job_id == game_id.to_string() && callback.randomness == record.randomness),
+    };
+
+    Ok(VerifyDrawResponse {
+        valid: randomness_valid && winner_indexes == record.winner_indexes && winners == record.winners,
+        draw_mode: game_state.draw_mode,
+        secret_hash: game_state.secret_hash,
+        winner_indexes,
+        winners,
+        record,
+    })
+}
+
+pub fn query_vault(deps: Deps) -> StdResult<VaultResponse> {
+    let pools = VAULT.range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|item| item.map(|(pool, vault_pool)| VaultPoolResponse {
+            pool,
+            nfts: vault_pool.nfts,
+            reserved: vault_pool.reserved,
+        }))
+        .collect::<StdResult<Vec<VaultPoolResponse>>>()?;
+
+    Ok(VaultResponse { pools })
+}
+
+// Balance, liabilities and withdrawable amount of every native denom held and every asset owed
+pub fn query_treasury(deps: Deps, env: Env) -> StdResult<TreasuryResponse> {
+    let liabilities = LIABILITIES.may_load(deps.storage)?.unwrap_or_default();
+    let mut assets: Vec<AssetInfo> = deps.querier.query_all_balances(&env.contract.address)?.into_iter()
+        .map(|balance| AssetInfo::Native { denom: balance.denom })
+        .collect();
+    for liability in liabilities.iter() {
+        if !assets.contains(&liability.asset) {
+            assets.push(liability.asset.clone());
+        }
+    }
+
+    let mut treasury: Vec<TreasuryAsset> = Vec::new();
+    for asset in assets {
+        let balance = asset_balance(&deps.querier, &env.contract.address, &asset)?;
+        let owed = asset_amount(&liabilities, &asset);
+        treasury.push(TreasuryAsset {
+            asset,
+            balance,
+            liabilities: owed,
+            withdrawable: balance.saturating_sub(owed),
+        });
+    }
+
+    Ok(TreasuryResponse { assets: treasury })
+}
+
+pub fn query_allowed_collections(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<AllowedCollectionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = match start_after {
+        Some(start_after) => Some(Bound::exclusive(deps.api.addr_validate(&start_after)?)),
+        None => None,
+    };
+    let collections = ALLOWED_COLLECTIONS.keys(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<Addr>>>()?;
+
+    Ok(AllowedCollectionsResponse { collections })
+}
+
+pub fn query_allowed_tokens(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<AllowedTokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = match start_after {
+        Some(start_after) => Some(Bound::exclusive(deps.api.addr_validate(&start_after)?)),
+        None => None,
+    };
+    let tokens = ALLOWED_TOKENS.keys(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<Addr>>>()?;
+
+    Ok(AllowedTokensResponse { tokens })
+}
+
+pub fn query_roles(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<RolesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = match start_after {
+        Some(start_after) => Some(Bound::exclusive(deps.api.addr_validate(&start_after)?)),
+        None => None,
+    };
+    let roles = ROLES.range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(address, roles)| RoleAssignment { address, roles }))
+        .collect::<StdResult<Vec<RoleAssignment>>>()?;
+
+    Ok(RolesResponse { roles })
+}
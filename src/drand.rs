@@ -0,0 +1,62 @@
+use std::convert::TryFrom;
+
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use bls12_381::{pairing, G1Affine, G2Affine, G2Projective};
+use sha2::{Digest, Sha256};
+use sha2_v09::Sha256 as Sha256V09;
+
+// Domain separation tag of the drand mainnet chained scheme (public key on G1, signatures on G2)
+const DRAND_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+// The message signed for a round is sha256(previous_signature || round)
+fn round_message(round: u64, previous_signature: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(previous_signature);
+    hasher.update(round.to_be_bytes());
+    hasher.finalize().into()
+}
+
+fn hash_round_to_curve(round: u64, previous_signature: &[u8]) -> G2Projective {
+    <G2Projective as HashToCurve<ExpandMsgXmd<Sha256V09>>>::hash_to_curve(
+        round_message(round, previous_signature),
+        DRAND_DST,
+    )
+}
+
+fn decode_public_key(public_key: &[u8]) -> Option<G1Affine> {
+    <[u8; 48]>::try_from(public_key).ok().and_then(|bytes| Option::from(G1Affine::from_compressed(&bytes)))
+}
+
+// Whether the bytes are a compressed G1 point, anything else would make every beacon fail
+pub fn is_valid_public_key(public_key: &[u8]) -> bool {
+    decode_public_key(public_key).is_some()
+}
+
+// Checks the BLS signature of a drand round against the network's public key
+pub fn verify_beacon(public_key: &[u8], round: u64, previous_signature: &[u8], signature: &[u8]) -> bool {
+    let public_key = match decode_public_key(public_key) {
+        Some(public_key) => public_key,
+        None => return false,
+    };
+    let signature = match <[u8; 96]>::try_from(signature).ok().and_then(|bytes| Option::from(G2Affine::from_compressed(&bytes))) {
+        Some(signature) => signature,
+        None => return false,
+    };
+    let message = G2Affine::from(hash_round_to_curve(round, previous_signature));
+
+    pairing(&G1Affine::generator(), &signature) == pairing(&public_key, &message)
+}
+
+// Randomness of a round as published by drand
+pub fn beacon_randomness(signature: &[u8]) -> [u8; 32] {
+    Sha256::digest(signature).into()
+}
+
+// First round published strictly after `time` (milliseconds, like end_time)
+pub fn first_round_after(time: u64, genesis_time: u64, period: u64) -> u64 {
+    let genesis_ms = genesis_time * 1000;
+    if time < genesis_ms {
+        return 1;
+    }
+    (time - genesis_ms) / (period * 1000) + 2
+}
@@ -12,41 +12,158 @@ pub enum ContractError {
     // #[error("Custom Error val: {val:?}")]
     // CustomError { val: String },
 
-    // #[error("Wrong Game Id")]
-    // WrongGameId {},
+    #[error("Wrong Game Id")]
+    WrongGameId {},
 
-    // #[error("Raffle already ended")]
-    // RaffleEnded {},
+    #[error("Raffle already ended")]
+    RaffleEnded {},
 
-    // #[error("Raffle Time Over")]
-    // RaffleTimeOver {},
+    #[error("Raffle Time Over")]
+    RaffleTimeOver {},
 
     // #[error("Raffle not started yet")]
     // RaffleNotActive {},
-    
-    // #[error("All raffle tickets was sold.")]
-    // RaffleSoldOut {},
 
-    // #[error("It is not the end time of the game")]
-    // CantFinishGame {},
+    #[error("All raffle tickets was sold.")]
+    RaffleSoldOut {},
 
-    // #[error("Can not access prize NFT")]
-    // CantAccessPrize {},
+    #[error("It is not the end time of the game")]
+    CantFinishGame {},
 
-    // #[error("Incorrect Funds")]
-    // IncorrectFunds {},
+    #[error("Can not access prize NFT")]
+    CantAccessPrize {},
+
+    #[error("Incorrect Funds")]
+    IncorrectFunds {},
+
+    #[error("Ticket price must be greater than zero")]
+    ZeroTicketPrice {},
+
+    #[error("Every payment asset needs exactly one price")]
+    InvalidTicketPrices {},
+
+    #[error("Tickets of this raffle are paid with a different asset")]
+    WrongPaymentAsset {},
 
     // #[error("Must send exactly {ticket_price} SEI to enter the raffle")]
     // PayError { ticket_price: u64 },
 
     // #[error("No participants in the raffle")]
     // NoParticipants {},
-    
+
     // #[error("Missing NFT contract address")]
-    // MissingNftContractAddr {}, 
+    // MissingNftContractAddr {},
 
     #[error("Can not transfer tokens until raffle is finished")]
     CantTransferTokens {},
+
+    #[error("Platform fee can not exceed 10000 bps")]
+    InvalidPlatformFee {},
+
+    #[error("Default denom must be set and min_duration can not exceed max_duration")]
+    InvalidConfig {},
+
+    #[error("No ownership transfer is pending")]
+    NoPendingOwner {},
+
+    #[error("Ownership proposal expired")]
+    OwnershipProposalExpired {},
+
+    #[error("Raffle duration is outside the allowed limits")]
+    InvalidRaffleDuration {},
+
+    #[error("Minimum sales can not exceed a sold out raffle")]
+    InvalidMinimumSales {},
+
+    #[error("Payout split needs 1 to 10 recipients with a weight above zero")]
+    InvalidPayoutSplit {},
+
+    #[error("Amount exceeds the balance not owed to raffles")]
+    InsufficientFreeBalance {},
+
+    #[error("Only operators can start commit-reveal raffles")]
+    CommitRevealNotAllowed {},
+
+    #[error("Secret hash must be a hex encoded sha256 digest")]
+    InvalidSecretHash {},
+
+    #[error("The secret must be revealed to select a winner")]
+    MissingSecret {},
+
+    #[error("Revealed secret does not match the committed hash")]
+    InvalidSecret {},
+
+    #[error("A drand beacon must be submitted to select a winner")]
+    MissingBeacon {},
+
+    #[error("Drand is not configured")]
+    DrandNotConfigured {},
+
+    #[error("Drand config needs a compressed G1 public key and a period above zero")]
+    InvalidDrandConfig {},
+
+    #[error("Expected drand round {expected}")]
+    WrongDrandRound { expected: u64 },
+
+    #[error("Invalid drand beacon signature")]
+    InvalidBeacon {},
+
+    #[error("Randomness proxy is not configured")]
+    ProxyNotConfigured {},
+
+    #[error("Randomness was already requested for this raffle")]
+    RandomnessPending {},
+
+    #[error("Randomness was not requested for this raffle")]
+    RandomnessNotRequested {},
+
+    #[error("Prizes can not be changed once tickets are sold")]
+    PrizesLocked {},
+
+    #[error("Pot shares must be above zero and add up to at most 10000 bps")]
+    InvalidPotShare {},
+
+    #[error("NFT and CW20 prizes must be sent to the contract")]
+    PrizeNotEscrowed {},
+
+    #[error("Prize placement does not exist")]
+    WrongPrizePlace {},
+
+    #[error("Raffle creation is disabled")]
+    RaffleCreationDisabled {},
+
+    #[error("Creation fee credit is too low")]
+    InsufficientCreationCredit {},
+
+    #[error("NFT collection is not allowed")]
+    CollectionNotAllowed {},
+
+    #[error("CW20 token is not allowed")]
+    TokenNotAllowed {},
+
+    #[error("Vault pool does not exist")]
+    WrongVaultPool {},
+
+    #[error("Every NFT of the vault pool is already reserved")]
+    VaultPoolEmpty {},
+
+    #[error("Raffle is paused")]
+    Paused {},
+
+    #[error("Ownership can not be renounced while the contract or a game is paused")]
+    PausedAtRenounce {},
+
+    #[error("Raffle can not be cancelled anymore")]
+    CantCancelRaffle {},
+
+    #[error("Raffle is not refunding")]
+    RaffleNotRefunding {},
+
+    #[error("No tickets to refund")]
+    NoTicketsToRefund {},
+
+    #[error("Refund already claimed")]
+    RefundAlreadyClaimed {},
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
@@ -4,7 +4,6 @@ mod tests {
     use crate::msg::InstantiateMsg;
     use cosmwasm_std::{Addr, Coin, Empty, Uint128};
     use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
-    use sha2::{Sha256, Digest};
 
 
     pub fn contract_template() -> Box<dyn Contract<Empty>> {
@@ -16,8 +15,8 @@ mod tests {
         Box::new(contract)
     }
 
-    const USER: &str = "USER";
-    const ADMIN: &str = "ADMIN";
+    const USER: &str = "user";
+    const ADMIN: &str = "admin";
     const NATIVE_DENOM: &str = "denom";
 
     fn mock_app() -> App {
@@ -36,17 +35,24 @@ mod tests {
         })
     }
 
+    fn instantiate_msg(randomness_proxy: Option<String>) -> InstantiateMsg {
+        InstantiateMsg {
+            admin: ADMIN.to_string(),
+            default_denom: "usei".to_string(),
+            platform_fee_bps: 0,
+            fee_recipient: None,
+            creation_fee: None,
+            min_duration: 0,
+            max_duration: 30 * 24 * 60 * 60 * 1000,
+            drand: None,
+            randomness_proxy,
+        }
+    }
+
     fn proper_instantiate() -> (App, CwTemplateContract) {
         let mut app = mock_app();
         let cw_template_id = app.store_code(contract_template());
-        let owner = Addr::unchecked("sei1hkwafxahtra74nhtxwwej5p28jyhvev8tl6ed5");
-        let sender_str = "";
-        let data_to_hash = format!("{}{}", sender_str, "sei1j7ah3st8qjr792qjwtnjmj65rqhpedjqf9dnsddj");
-        let mut hasher = Sha256::new();
-        hasher.update(data_to_hash.as_bytes());
-        let result_hash = hasher.finalize();
-        let authkey = hex::encode(result_hash);
-        let msg = InstantiateMsg { owner, authkey };
+        let msg = instantiate_msg(None);
         let cw_template_contract_addr = app
             .instantiate_contract(
                 cw_template_id,
@@ -65,15 +71,1062 @@ mod tests {
 
     mod count {
         use super::*;
-        use crate::msg::ExecuteMsg;
 
         #[test]
         fn count() {
-            let (mut app, cw_template_contract) = proper_instantiate();
+            let (_app, _cw_template_contract) = proper_instantiate();
             
             // let msg = ExecuteMsg::Increment {};
             // let cosmos_msg = cw_template_contract.call(msg).unwrap();
             // app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
         }
     }
+
+    mod raffle {
+        use super::*;
+        use crate::msg::{AllowedCollectionsResponse, ConfigUpdate, Cw20HookMsg, Cw2981Extension, Cw2981QueryMsg, DrandBeacon, ExecuteMsg, GameResponse, GlobalResponse, NoisCallback, PendingOwnerResponse, ProxyExecuteMsg, QueryMsg, RaffleParams, ReceiveNftMsg, RolesResponse, RoyaltiesInfoResponse, TreasuryResponse, VaultResponse, VerifyDrawResponse};
+        use crate::state::{AssetAmount, AssetInfo, Config, DrandConfig, DrawMode, GameStatus, PayoutShare, PrizeItem, Role, TicketPrice, RAFFLE_ENDED};
+        use cosmwasm_std::{coins, to_json_binary, Binary, Deps, DepsMut, Env, HexBinary, MessageInfo, Response, StdError, StdResult, WasmMsg};
+        use cw20::{Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
+        use cw721::{Cw721ExecuteMsg, Cw721ReceiveMsg};
+        use cw_storage_plus::Map;
+        use sha2::{Digest, Sha256};
+        use super::drand::{GENESIS_TIME, PERIOD, PREVIOUS_SIGNATURE, PUBLIC_KEY, ROUND, SIGNATURE};
+        use crate::ContractError;
+
+        const RANDOMNESS: &str = "8b676484b5fb1f37f9ec5c413d7d29883504e5b669f604a1ce68b3388e9ae3d9";
+
+        fn noop_instantiate(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+            Ok(Response::new())
+        }
+
+        fn noop_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+            to_json_binary(&Empty {})
+        }
+
+        // Stands in for the live randomness service and answers every job right away
+        fn proxy_execute(_deps: DepsMut, env: Env, info: MessageInfo, msg: ProxyExecuteMsg) -> StdResult<Response> {
+            match msg {
+                ProxyExecuteMsg::GetNextRandomness { job_id } => {
+                    let callback = ExecuteMsg::NoisReceive {
+                        callback: NoisCallback {
+                            job_id,
+                            published: env.block.time,
+                            randomness: HexBinary::from_hex(RANDOMNESS)?,
+                        },
+                    };
+                    Ok(Response::new().add_message(WasmMsg::Execute {
+                        contract_addr: info.sender.into_string(),
+                        msg: to_json_binary(&callback)?,
+                        funds: vec![],
+                    }))
+                }
+            }
+        }
+
+        // Randomness service that hasn't published the job yet
+        fn silent_proxy_execute(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: ProxyExecuteMsg) -> StdResult<Response> {
+            Ok(Response::new())
+        }
+
+        // Accepts every transfer of the escrowed prize
+        fn nft_execute(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Cw721ExecuteMsg) -> StdResult<Response> {
+            Ok(Response::new())
+        }
+
+        // 10% cw2981 royalty on every token
+        fn nft_query(_deps: Deps, _env: Env, msg: Cw2981QueryMsg) -> StdResult<Binary> {
+            match msg {
+                Cw2981QueryMsg::Extension { msg: Cw2981Extension::RoyaltyInfo { sale_price, .. } } => to_json_binary(&RoyaltiesInfoResponse {
+                    address: "artist".to_string(),
+                    royalty_amount: sale_price.multiply_ratio(10u128, 100u128),
+                }),
+            }
+        }
+
+        const CW20_BALANCES: Map<&Addr, Uint128> = Map::new("balances");
+
+        // Just enough of a cw20 to pay through Send and to be paid back through Transfer
+        fn cw20_instantiate(deps: DepsMut, _env: Env, _info: MessageInfo, msg: Vec<Cw20Coin>) -> StdResult<Response> {
+            for balance in msg {
+                CW20_BALANCES.save(deps.storage, &Addr::unchecked(balance.address), &balance.amount)?;
+            }
+            Ok(Response::new())
+        }
+
+        fn cw20_move(deps: DepsMut, from: &Addr, to: &Addr, amount: Uint128) -> StdResult<()> {
+            let from_balance = CW20_BALANCES.may_load(deps.storage, from)?.unwrap_or_default().checked_sub(amount)?;
+            CW20_BALANCES.save(deps.storage, from, &from_balance)?;
+            let to_balance = CW20_BALANCES.may_load(deps.storage, to)?.unwrap_or_default() + amount;
+            CW20_BALANCES.save(deps.storage, to, &to_balance)
+        }
+
+        fn cw20_execute(deps: DepsMut, _env: Env, info: MessageInfo, msg: Cw20ExecuteMsg) -> StdResult<Response> {
+            match msg {
+                Cw20ExecuteMsg::Transfer { recipient, amount } => {
+                    cw20_move(deps, &info.sender, &Addr::unchecked(recipient), amount)?;
+                    Ok(Response::new())
+                },
+                Cw20ExecuteMsg::Send { contract, amount, msg } => {
+                    cw20_move(deps, &info.sender, &Addr::unchecked(contract.clone()), amount)?;
+                    let receive = Cw20ReceiveMsg { sender: info.sender.into_string(), amount, msg };
+                    Ok(Response::new().add_message(receive.into_cosmos_msg(contract)?))
+                },
+                _ => Err(StdError::generic_err("unsupported")),
+            }
+        }
+
+        fn cw20_query(deps: Deps, _env: Env, msg: Cw20QueryMsg) -> StdResult<Binary> {
+            match msg {
+                Cw20QueryMsg::Balance { address } => to_json_binary(&cw20::BalanceResponse {
+                    balance: CW20_BALANCES.may_load(deps.storage, &Addr::unchecked(address))?.unwrap_or_default(),
+                }),
+                _ => Err(StdError::generic_err("unsupported")),
+            }
+        }
+
+        // Token contract with 1000 tokens in the user's wallet
+        fn cw20_token(app: &mut App) -> Addr {
+            let cw20_id = app.store_code(Box::new(ContractWrapper::new(cw20_execute, cw20_instantiate, cw20_query)));
+            let balances = vec![Cw20Coin { address: USER.to_string(), amount: Uint128::new(1000) }];
+            app.instantiate_contract(cw20_id, Addr::unchecked(ADMIN), &balances, &[], "token", None)
+                .unwrap()
+        }
+
+        fn cw20_balance(app: &App, token: &Addr, address: &str) -> Uint128 {
+            let res: cw20::BalanceResponse = app
+                .wrap()
+                .query_wasm_smart(token, &Cw20QueryMsg::Balance { address: address.to_string() })
+                .unwrap();
+            res.balance
+        }
+
+        fn raffle_params(app: &App) -> RaffleParams {
+            RaffleParams {
+                prices: vec![TicketPrice {
+                    asset: AssetInfo::Native { denom: "usei".to_string() },
+                    price: Uint128::new(100),
+                }],
+                total_ticket_count: 10,
+                collection_wallet: ADMIN.to_string(),
+                end_time: (app.block_info().time.seconds() + 60) * 1000,
+                min_tickets_sold: None,
+                reserve_amount: None,
+                draw_mode: DrawMode::Proxy,
+                secret_hash: None,
+                payout_split: None,
+            }
+        }
+
+        // Creator secret of the commit-reveal games
+        const SECRET: &str = "raffle secret";
+
+        fn commit_reveal_params(app: &App) -> RaffleParams {
+            let mut params = raffle_params(app);
+            params.draw_mode = DrawMode::CommitReveal;
+            params.secret_hash = Some(hex::encode(Sha256::digest(SECRET.as_bytes())));
+            params
+        }
+
+        fn setup() -> (App, Addr, Addr) {
+            let mut app = AppBuilder::new().build(|router, _, storage| {
+                router
+                    .bank
+                    .init_balance(storage, &Addr::unchecked(USER), vec![Coin::new(1000, "uatom"), Coin::new(1000, "usei")])
+                    .unwrap();
+            });
+            let proxy_id = app.store_code(Box::new(ContractWrapper::new(proxy_execute, noop_instantiate, noop_query)));
+            let raffle_id = app.store_code(contract_template());
+            let nft_id = app.store_code(Box::new(ContractWrapper::new(nft_execute, noop_instantiate, nft_query)));
+
+            let proxy = app
+                .instantiate_contract(proxy_id, Addr::unchecked(ADMIN), &Empty {}, &[], "proxy", None)
+                .unwrap();
+
+            let msg = instantiate_msg(Some(proxy.to_string()));
+            let raffle = app
+                .instantiate_contract(raffle_id, Addr::unchecked(ADMIN), &msg, &[], "raffle", None)
+                .unwrap();
+            let nft = app
+                .instantiate_contract(nft_id, Addr::unchecked(ADMIN), &Empty {}, &[], "nft", None)
+                .unwrap();
+
+            let allow = ExecuteMsg::AddAllowedCollection { contract_addr: nft.to_string() };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &allow, &[]).unwrap();
+
+            let params = raffle_params(&app);
+            // What the cw721 contract sends on SendNft
+            app.execute_contract(
+                nft.clone(),
+                raffle.clone(),
+                &ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                    sender: ADMIN.to_string(),
+                    token_id: "1".to_string(),
+                    msg: to_json_binary(&ReceiveNftMsg::StartRaffle(params)).unwrap(),
+                }),
+                &[],
+            )
+            .unwrap();
+            app.execute_contract(
+                nft.clone(),
+                raffle.clone(),
+                &ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                    sender: ADMIN.to_string(),
+                    token_id: "2".to_string(),
+                    msg: to_json_binary(&ReceiveNftMsg::AddPrize { game_id: 1, place: None }).unwrap(),
+                }),
+                &[],
+            )
+            .unwrap();
+            // Bundled with the first placement's NFT
+            app.execute_contract(
+                nft,
+                raffle.clone(),
+                &ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                    sender: ADMIN.to_string(),
+                    token_id: "3".to_string(),
+                    msg: to_json_binary(&ReceiveNftMsg::AddPrize { game_id: 1, place: Some(1) }).unwrap(),
+                }),
+                &[],
+            )
+            .unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id: 1 }, &coins(300, "usei"))
+                .unwrap();
+
+            (app, raffle, proxy)
+        }
+
+        #[test]
+        fn winner_is_drawn_in_callback() {
+            let (mut app, raffle, _proxy) = setup();
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+            let finalize = ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id: 1, secret: None, beacon: None };
+
+            // Only keepers and the creator can finalize right after end_time
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &finalize, &[]).unwrap_err();
+            let grant = ExecuteMsg::GrantRole { address: "keeper".to_string(), role: Role::Keeper };
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &grant, &[]).unwrap_err();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &grant, &[]).unwrap();
+            let roles: RolesResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetRoles { start_after: None, limit: None }).unwrap();
+            assert_eq!(roles.roles[0].roles, vec![Role::Keeper]);
+
+            let res = app.execute_contract(Addr::unchecked("keeper"), raffle.clone(), &finalize, &[]).unwrap();
+            assert!(res.events.iter().any(|event| event.attributes.iter().any(|attr| attr.key == "place_2_ticket")));
+            assert!(res.events.iter().any(|event| event.attributes.iter().any(|attr| attr.key == "place_1_prize" && attr.value.contains("/1,") && attr.value.ends_with("/3"))));
+
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetGameInfo { game_id: 1 }).unwrap();
+            assert_eq!(game.raffle_status, RAFFLE_ENDED);
+            assert_eq!(game.winners, vec![Addr::unchecked(USER), Addr::unchecked(USER)]);
+            // The royalty is paid before the collection wallet
+            assert_eq!(app.wrap().query_balance("artist", "usei").unwrap().amount, Uint128::new(30));
+            assert_eq!(app.wrap().query_balance(ADMIN, "usei").unwrap().amount, Uint128::new(270));
+
+            let draw: VerifyDrawResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::VerifyDraw { game_id: 1 }).unwrap();
+            assert!(draw.valid);
+            assert_eq!(draw.record.randomness, RANDOMNESS);
+            assert_eq!(draw.record.callback.map(|callback| callback.job_id), Some("1".to_string()));
+            assert_ne!(draw.winner_indexes[0], draw.winner_indexes[1]);
+
+            // The draw can't be triggered a second time
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                raffle,
+                &ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id: 1, secret: None, beacon: None },
+                &[],
+            )
+            .unwrap_err();
+        }
+
+        #[test]
+        fn winner_is_drawn_with_revealed_secret() {
+            let (mut app, raffle, _proxy) = setup();
+            let start = ExecuteMsg::StartRaffle { params: commit_reveal_params(&app), prize: PrizeItem::PotShare { bps: 5000 } };
+            // The creator knows the secret, so wallets without a role can't start such a game
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &start, &[]).unwrap_err();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id: 2 }, &coins(300, "usei"))
+                .unwrap();
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+
+            let finalize = |secret: Option<&str>| ExecuteMsg::SelectWinnerAndTransferNFTtoWinner {
+                game_id: 2,
+                secret: secret.map(|secret| secret.to_string()),
+                beacon: None,
+            };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &finalize(None), &[]).unwrap_err();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &finalize(Some("wrong secret")), &[]).unwrap_err();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &finalize(Some(SECRET)), &[]).unwrap();
+
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetGameInfo { game_id: 2 }).unwrap();
+            assert_eq!(game.raffle_status, RAFFLE_ENDED);
+            assert_eq!(game.winners, vec![Addr::unchecked(USER)]);
+            let draw: VerifyDrawResponse = app.wrap().query_wasm_smart(raffle, &QueryMsg::VerifyDraw { game_id: 2 }).unwrap();
+            assert!(draw.valid);
+            assert_eq!(draw.draw_mode, DrawMode::CommitReveal);
+            assert_eq!(hex::encode(Sha256::digest(draw.record.randomness.as_bytes())), draw.secret_hash);
+        }
+
+        #[test]
+        fn unrevealed_secret_refunds_buyers() {
+            let (mut app, raffle, _proxy) = setup();
+            for game_id in [2, 3] {
+                let start = ExecuteMsg::StartRaffle { params: commit_reveal_params(&app), prize: PrizeItem::PotShare { bps: 5000 } };
+                app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap();
+                app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id }, &coins(100, "usei"))
+                    .unwrap();
+            }
+            // Past end_time and the whole reveal window
+            app.update_block(|block| block.time = block.time.plus_seconds(120 + 24 * 60 * 60));
+
+            // A late reveal is no longer drawn, the game falls back to refunds
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                raffle.clone(),
+                &ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id: 2, secret: Some(SECRET.to_string()), beacon: None },
+                &[],
+            )
+            .unwrap();
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetGameInfo { game_id: 2 }).unwrap();
+            assert_eq!(game.status, GameStatus::Refunding);
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::ClaimRefund { game_id: 2 }, &[])
+                .unwrap();
+
+            // Buyers of a game nobody finalized claim right away
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::ClaimRefund { game_id: 3 }, &[])
+                .unwrap();
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle, &QueryMsg::GetGameInfo { game_id: 3 }).unwrap();
+            assert_eq!(game.status, GameStatus::Refunding);
+            assert_eq!(app.wrap().query_balance(USER, "usei").unwrap().amount, Uint128::new(700));
+        }
+
+        #[test]
+        fn unsold_raffle_returns_prize_to_owner() {
+            let (mut app, raffle, _proxy) = setup();
+            let allowed: AllowedCollectionsResponse = app
+                .wrap()
+                .query_wasm_smart(raffle.clone(), &QueryMsg::GetAllowedCollections { start_after: None, limit: None })
+                .unwrap();
+            let nft = allowed.collections[0].clone();
+            let start = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: ADMIN.to_string(),
+                token_id: "4".to_string(),
+                msg: to_json_binary(&ReceiveNftMsg::StartRaffle(raffle_params(&app))).unwrap(),
+            });
+            app.execute_contract(nft.clone(), raffle.clone(), &start, &[]).unwrap();
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+
+            let res = app.execute_contract(
+                Addr::unchecked(ADMIN),
+                raffle.clone(),
+                &ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id: 2, secret: None, beacon: None },
+                &[],
+            )
+            .unwrap();
+            // The escrowed NFT is sent back instead of requesting randomness
+            assert!(res.events.iter().any(|event| event.ty == "execute" && event.attributes.iter().any(|attr| attr.value == nft.as_str())));
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle, &QueryMsg::GetGameInfo { game_id: 2 }).unwrap();
+            assert_eq!(game.status, GameStatus::NoSales);
+            assert!(game.winners.is_empty());
+        }
+
+        #[test]
+        fn raffle_below_minimum_sales_refunds_buyers() {
+            let (mut app, raffle, _proxy) = setup();
+
+            let mut params = raffle_params(&app);
+            params.min_tickets_sold = Some(11);
+            let start = ExecuteMsg::StartRaffle { params, prize: PrizeItem::PotShare { bps: 5000 } };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap_err();
+
+            let mut params = raffle_params(&app);
+            params.min_tickets_sold = Some(5);
+            let start = ExecuteMsg::StartRaffle { params, prize: PrizeItem::PotShare { bps: 5000 } };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id: 2 }, &coins(400, "usei"))
+                .unwrap();
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                raffle.clone(),
+                &ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id: 2, secret: None, beacon: None },
+                &[],
+            )
+            .unwrap();
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetGameInfo { game_id: 2 }).unwrap();
+            assert_eq!(game.status, GameStatus::Refunding);
+            assert!(game.winners.is_empty());
+
+            app.execute_contract(Addr::unchecked(USER), raffle, &ExecuteMsg::ClaimRefund { game_id: 2 }, &[])
+                .unwrap();
+            assert_eq!(app.wrap().query_balance(USER, "usei").unwrap().amount, Uint128::new(700));
+        }
+
+        #[test]
+        fn cw20_overpayment_is_refunded_in_tokens() {
+            let (mut app, raffle, _proxy) = setup();
+            let token = cw20_token(&mut app);
+
+            let mut params = raffle_params(&app);
+            params.prices = vec![TicketPrice { asset: AssetInfo::Cw20 { contract_addr: token.clone() }, price: Uint128::new(100) }];
+            let start = ExecuteMsg::StartRaffle { params, prize: PrizeItem::PotShare { bps: 5000 } };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap();
+
+            // Two tickets, the 50 left over go back to the buyer
+            let enter = Cw20ExecuteMsg::Send {
+                contract: raffle.to_string(),
+                amount: Uint128::new(250),
+                msg: to_json_binary(&Cw20HookMsg::EnterRaffle { game_id: 2 }).unwrap(),
+            };
+            app.execute_contract(Addr::unchecked(USER), token.clone(), &enter, &[]).unwrap();
+            assert_eq!(cw20_balance(&app, &token, USER), Uint128::new(800));
+            assert_eq!(cw20_balance(&app, &token, raffle.as_str()), Uint128::new(200));
+
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetGameInfo { game_id: 2 }).unwrap();
+            assert_eq!(game.sold_ticket_count, 2);
+            assert_eq!(game.proceeds[0].amount, Uint128::new(200));
+
+            // A native payment isn't accepted for a cw20 priced raffle
+            app.execute_contract(Addr::unchecked(USER), raffle, &ExecuteMsg::EnterRaffle { game_id: 2 }, &coins(100, "usei"))
+                .unwrap_err();
+        }
+
+        #[test]
+        fn tickets_are_paid_with_one_coin_of_the_game_denom() {
+            let (mut app, raffle, _proxy) = setup();
+
+            let enter = ExecuteMsg::EnterRaffle { game_id: 1 };
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &enter, &coins(100, "uatom")).unwrap_err();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &enter, &[Coin::new(100, "uatom"), Coin::new(100, "usei")])
+                .unwrap_err();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &enter, &[]).unwrap_err();
+            assert_eq!(app.wrap().query_balance(USER, "uatom").unwrap().amount, Uint128::new(1000));
+            assert_eq!(app.wrap().query_balance(USER, "usei").unwrap().amount, Uint128::new(700));
+
+            // Every game picks its own denom
+            let mut params = raffle_params(&app);
+            params.prices = vec![TicketPrice { asset: AssetInfo::Native { denom: "uatom".to_string() }, price: Uint128::new(40) }];
+            let start = ExecuteMsg::StartRaffle { params, prize: PrizeItem::PotShare { bps: 5000 } };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id: 2 }, &coins(100, "usei"))
+                .unwrap_err();
+            app.execute_contract(Addr::unchecked(USER), raffle, &ExecuteMsg::EnterRaffle { game_id: 2 }, &coins(100, "uatom"))
+                .unwrap();
+            assert_eq!(app.wrap().query_balance(USER, "uatom").unwrap().amount, Uint128::new(920));
+        }
+
+        #[test]
+        fn proceeds_are_tracked_per_asset() {
+            let (mut app, raffle, _proxy) = setup();
+
+            let mut params = raffle_params(&app);
+            params.prices.push(TicketPrice { asset: AssetInfo::Native { denom: "uatom".to_string() }, price: Uint128::new(10) });
+            let start = ExecuteMsg::StartRaffle { params, prize: PrizeItem::PotShare { bps: 5000 } };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id: 2 }, &coins(200, "usei"))
+                .unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id: 2 }, &coins(30, "uatom"))
+                .unwrap();
+
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetGameInfo { game_id: 2 }).unwrap();
+            assert_eq!(game.sold_ticket_count, 5);
+            assert_eq!(game.proceeds, vec![
+                AssetAmount { asset: AssetInfo::Native { denom: "usei".to_string() }, amount: Uint128::new(200) },
+                AssetAmount { asset: AssetInfo::Native { denom: "uatom".to_string() }, amount: Uint128::new(30) },
+            ]);
+
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                raffle.clone(),
+                &ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id: 2, secret: None, beacon: None },
+                &[],
+            )
+            .unwrap();
+
+            // Half of each asset goes to the winner, the other half to the collection wallet
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle, &QueryMsg::GetGameInfo { game_id: 2 }).unwrap();
+            assert_eq!(game.paid_out, vec![
+                AssetAmount { asset: AssetInfo::Native { denom: "usei".to_string() }, amount: Uint128::new(100) },
+                AssetAmount { asset: AssetInfo::Native { denom: "uatom".to_string() }, amount: Uint128::new(15) },
+            ]);
+            assert_eq!(app.wrap().query_balance(USER, "uatom").unwrap().amount, Uint128::new(985));
+            assert_eq!(app.wrap().query_balance(ADMIN, "uatom").unwrap().amount, Uint128::new(15));
+            assert_eq!(app.wrap().query_balance(ADMIN, "usei").unwrap().amount, Uint128::new(100));
+        }
+
+        #[test]
+        fn cancelled_raffle_refunds_buyers() {
+            let (mut app, raffle, _proxy) = setup();
+
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::CancelRaffle { game_id: 1 }, &[])
+                .unwrap_err();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &ExecuteMsg::CancelRaffle { game_id: 1 }, &[])
+                .unwrap();
+
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::ClaimRefund { game_id: 1 }, &[])
+                .unwrap();
+            let balance = app.wrap().query_balance(USER, "usei").unwrap();
+            assert_eq!(balance.amount, Uint128::new(1000));
+
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::ClaimRefund { game_id: 1 }, &[])
+                .unwrap_err();
+
+            // A creator can't call their raffle off once tickets are sold
+            let start = ExecuteMsg::StartRaffle { params: raffle_params(&app), prize: PrizeItem::PotShare { bps: 5000 } };
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &start, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id: 2 }, &coins(100, "usei"))
+                .unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::CancelRaffle { game_id: 2 }, &[])
+                .unwrap_err();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle, &ExecuteMsg::CancelRaffle { game_id: 2 }, &[])
+                .unwrap();
+        }
+
+        #[test]
+        fn pot_share_goes_to_winner() {
+            let (mut app, raffle, _proxy) = setup();
+
+            // Pot shares are not escrowed, so nothing may be sent along
+            let start = ExecuteMsg::StartRaffle { params: raffle_params(&app), prize: PrizeItem::PotShare { bps: 5000 } };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id: 2 }, &coins(300, "usei"))
+                .unwrap();
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                raffle.clone(),
+                &ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id: 2, secret: None, beacon: None },
+                &[],
+            )
+            .unwrap();
+            let balance = app.wrap().query_balance(USER, "usei").unwrap();
+            assert_eq!(balance.amount, Uint128::new(550));
+
+            // The other half is paid to the collection wallet
+            let balance = app.wrap().query_balance(ADMIN, "usei").unwrap();
+            assert_eq!(balance.amount, Uint128::new(150));
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle, &QueryMsg::GetGameInfo { game_id: 2 }).unwrap();
+            assert!(game.proceeds[0].amount.is_zero());
+            assert_eq!(game.paid_out[0].amount, Uint128::new(150));
+        }
+
+        #[test]
+        fn mystery_box_is_drawn_from_vault() {
+            let (mut app, raffle, _proxy) = setup();
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetGameInfo { game_id: 1 }).unwrap();
+            let nft = match &game.prizes[0].items[0] {
+                PrizeItem::Nft { contract_addr, .. } => contract_addr.clone(),
+                _ => panic!("expected an NFT prize"),
+            };
+
+            for (sender, token_id) in [(ADMIN, "10"), (ADMIN, "11"), (USER, "12")] {
+                let deposit = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                    sender: sender.to_string(),
+                    token_id: token_id.to_string(),
+                    msg: to_json_binary(&ReceiveNftMsg::DepositToVault { pool: "box".to_string() }).unwrap(),
+                });
+                let res = app.execute_contract(nft.clone(), raffle.clone(), &deposit, &[]);
+                assert_eq!(res.is_ok(), sender == ADMIN);
+            }
+            // Called directly, the hook can't pass as the admin
+            let spoofed = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: ADMIN.to_string(),
+                token_id: "fake".to_string(),
+                msg: to_json_binary(&ReceiveNftMsg::DepositToVault { pool: "box".to_string() }).unwrap(),
+            });
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &spoofed, &[]).unwrap_err();
+
+            let start = ExecuteMsg::StartRaffle { params: raffle_params(&app), prize: PrizeItem::MysteryBox { pool: "box".to_string() } };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap();
+
+            // One of the two NFTs is reserved by the mystery box
+            let withdraw = |token_id: &str| ExecuteMsg::WithdrawFromVault {
+                pool: "box".to_string(),
+                contract_addr: nft.to_string(),
+                token_id: token_id.to_string(),
+            };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &withdraw("10"), &[]).unwrap();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &withdraw("11"), &[]).unwrap_err();
+
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id: 2 }, &coins(100, "usei"))
+                .unwrap();
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                raffle.clone(),
+                &ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id: 2, secret: None, beacon: None },
+                &[],
+            )
+            .unwrap();
+
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetGameInfo { game_id: 2 }).unwrap();
+            assert_eq!(game.prizes[0].items, vec![PrizeItem::Nft { contract_addr: nft, token_id: "11".to_string() }]);
+            let vault: VaultResponse = app.wrap().query_wasm_smart(raffle, &QueryMsg::GetVault {}).unwrap();
+            assert!(vault.pools[0].nfts.is_empty());
+            assert_eq!(vault.pools[0].reserved, 0);
+        }
+
+        #[test]
+        fn proceeds_are_split_after_platform_fee() {
+            let (mut app, raffle, _proxy) = setup();
+
+            let fee = ExecuteMsg::UpdateConfig(ConfigUpdate {
+                platform_fee_bps: Some(1000),
+                fee_recipient: Some("treasury".to_string()),
+                ..ConfigUpdate::default()
+            });
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &fee, &[]).unwrap_err();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &fee, &[]).unwrap();
+
+            let mut params = raffle_params(&app);
+            params.payout_split = Some(vec![
+                PayoutShare { recipient: Addr::unchecked("creator"), weight: 1 },
+                PayoutShare { recipient: Addr::unchecked("charity"), weight: 1 },
+            ]);
+            let start = ExecuteMsg::StartRaffle { params, prize: PrizeItem::PotShare { bps: 1000 } };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id: 2 }, &coins(300, "usei"))
+                .unwrap();
+            // Raising the fee after the sales doesn't touch the game's proceeds
+            let fee = ExecuteMsg::UpdateConfig(ConfigUpdate { platform_fee_bps: Some(10000), ..ConfigUpdate::default() });
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &fee, &[]).unwrap();
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                raffle.clone(),
+                &ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id: 2, secret: None, beacon: None },
+                &[],
+            )
+            .unwrap();
+
+            // 30 to the winner, 27 platform fee and 243 split evenly, the odd unit goes to the first recipient
+            for (wallet, amount) in [("treasury", 27), ("creator", 122), ("charity", 121)] {
+                assert_eq!(app.wrap().query_balance(wallet, "usei").unwrap().amount, Uint128::new(amount));
+            }
+
+            // Weights that can't be added up would make the raffle impossible to draw
+            let mut params = raffle_params(&app);
+            params.payout_split = Some(vec![
+                PayoutShare { recipient: Addr::unchecked("creator"), weight: u64::MAX },
+                PayoutShare { recipient: Addr::unchecked("charity"), weight: 1 },
+            ]);
+            let start = ExecuteMsg::StartRaffle { params, prize: PrizeItem::PotShare { bps: 1000 } };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle, &start, &[]).unwrap_err();
+        }
+
+        #[test]
+        fn any_wallet_can_start_a_raffle() {
+            let (mut app, raffle, _proxy) = setup();
+            let start = ExecuteMsg::StartRaffle { params: raffle_params(&app), prize: PrizeItem::PotShare { bps: 5000 } };
+
+            let settings = ExecuteMsg::UpdateConfig(ConfigUpdate { creation_fee: Some(Coin::new(50, "usei")), ..ConfigUpdate::default() });
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &settings, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &start, &[]).unwrap_err();
+
+            // The creation fee comes out of the prepaid credit
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::DepositCreationCredit {}, &coins(50, "usei"))
+                .unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &start, &[]).unwrap();
+            assert_eq!(app.wrap().query_balance(ADMIN, "usei").unwrap().amount, Uint128::new(50));
+
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetGameInfo { game_id: 2 }).unwrap();
+            assert_eq!(game.owner, Addr::unchecked(USER));
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::CancelRaffle { game_id: 2 }, &[]).unwrap();
+
+            let settings = ExecuteMsg::UpdateConfig(ConfigUpdate { creation_enabled: Some(false), ..ConfigUpdate::default() });
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &settings, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle, &start, &[]).unwrap_err();
+        }
+
+        #[test]
+        fn only_allowed_collections_are_prizes() {
+            let (mut app, raffle, _proxy) = setup();
+            let allowed: AllowedCollectionsResponse = app
+                .wrap()
+                .query_wasm_smart(raffle.clone(), &QueryMsg::GetAllowedCollections { start_after: None, limit: None })
+                .unwrap();
+            assert_eq!(allowed.collections.len(), 1);
+            let nft = allowed.collections[0].clone();
+
+            let remove = ExecuteMsg::RemoveAllowedCollection { contract_addr: nft.to_string() };
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &remove, &[]).unwrap_err();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &remove, &[]).unwrap();
+
+            let start = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: ADMIN.to_string(),
+                token_id: "4".to_string(),
+                msg: to_json_binary(&ReceiveNftMsg::StartRaffle(raffle_params(&app))).unwrap(),
+            });
+            app.execute_contract(nft, raffle, &start, &[]).unwrap_err();
+        }
+
+        #[test]
+        fn only_allowed_tokens_are_prizes() {
+            let (mut app, raffle, _proxy) = setup();
+            let token = cw20_token(&mut app);
+            let start = Cw20ExecuteMsg::Send {
+                contract: raffle.to_string(),
+                amount: Uint128::new(100),
+                msg: to_json_binary(&Cw20HookMsg::StartRaffle(raffle_params(&app))).unwrap(),
+            };
+            app.execute_contract(Addr::unchecked(USER), token.clone(), &start, &[]).unwrap_err();
+
+            let allow = ExecuteMsg::AddAllowedToken { contract_addr: token.to_string() };
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &allow, &[]).unwrap_err();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &allow, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), token, &start, &[]).unwrap();
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetGameInfo { game_id: 2 }).unwrap();
+            assert_eq!(game.owner, Addr::unchecked(USER));
+
+            // Called directly, the hook can't pass as the admin
+            let spoofed = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: ADMIN.to_string(),
+                amount: Uint128::new(100),
+                msg: to_json_binary(&Cw20HookMsg::AddPrize { game_id: 1, place: Some(1) }).unwrap(),
+            });
+            app.execute_contract(Addr::unchecked(USER), raffle, &spoofed, &[]).unwrap_err();
+        }
+
+        #[test]
+        fn randomness_sources_can_be_removed() {
+            let (mut app, raffle, _proxy) = setup();
+
+            // No upper limit on the raffle duration
+            let settings = ExecuteMsg::UpdateConfig(ConfigUpdate { max_duration: Some(u64::MAX), ..ConfigUpdate::default() });
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &settings, &[]).unwrap();
+            let start = ExecuteMsg::StartRaffle { params: raffle_params(&app), prize: PrizeItem::PotShare { bps: 5000 } };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap();
+
+            let settings = ExecuteMsg::UpdateConfig(ConfigUpdate { randomness_proxy: Some(String::new()), ..ConfigUpdate::default() });
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &settings, &[]).unwrap();
+            let config: Config = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetConfig {}).unwrap();
+            assert_eq!(config.randomness_proxy, None);
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap_err();
+
+            // Games already started are still drawn by the proxy they were started with
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                raffle.clone(),
+                &ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id: 1, secret: None, beacon: None },
+                &[],
+            )
+            .unwrap();
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle, &QueryMsg::GetGameInfo { game_id: 1 }).unwrap();
+            assert_eq!(game.status, GameStatus::Ended);
+        }
+
+        #[test]
+        fn ownership_moves_in_two_steps() {
+            let (mut app, raffle, _proxy) = setup();
+            let now = app.block_info().time.seconds() * 1000;
+
+            let propose = ExecuteMsg::ProposeNewOwner { owner: "multisig".to_string(), expires_at: Some(now + 60_000) };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &propose, &[]).unwrap();
+            let pending: PendingOwnerResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetPendingOwner {}).unwrap();
+            assert_eq!(pending.pending_owner, Some(Addr::unchecked("multisig")));
+
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::AcceptOwnership {}, &[]).unwrap_err();
+            // A proposal can't be accepted once it expired
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+            app.execute_contract(Addr::unchecked("multisig"), raffle.clone(), &ExecuteMsg::AcceptOwnership {}, &[]).unwrap_err();
+
+            let propose = ExecuteMsg::ProposeNewOwner { owner: "multisig".to_string(), expires_at: None };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &propose, &[]).unwrap();
+            app.execute_contract(Addr::unchecked("multisig"), raffle.clone(), &ExecuteMsg::AcceptOwnership {}, &[]).unwrap();
+            let global: GlobalResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetGlobalInfo {}).unwrap();
+            assert_eq!(global.owner, Some(Addr::unchecked("multisig")));
+
+            let grant = ExecuteMsg::GrantRole { address: "deputy".to_string(), role: Role::Admin };
+            app.execute_contract(Addr::unchecked("multisig"), raffle.clone(), &grant, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &ExecuteMsg::RenounceOwnership {}, &[]).unwrap_err();
+            app.execute_contract(Addr::unchecked("multisig"), raffle.clone(), &ExecuteMsg::RenounceOwnership {}, &[]).unwrap();
+            // Delegated admins lose their powers along with the owner
+            app.execute_contract(Addr::unchecked("deputy"), raffle.clone(), &ExecuteMsg::Pause {}, &[]).unwrap_err();
+            let roles: RolesResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetRoles { start_after: None, limit: None }).unwrap();
+            assert!(roles.roles.is_empty());
+            app.execute_contract(Addr::unchecked("multisig"), raffle.clone(), &propose, &[]).unwrap_err();
+            let global: GlobalResponse = app.wrap().query_wasm_smart(raffle, &QueryMsg::GetGlobalInfo {}).unwrap();
+            assert_eq!(global.owner, None);
+        }
+
+        #[test]
+        fn ownership_cant_be_renounced_while_paused() {
+            let (mut app, raffle, _proxy) = setup();
+            let renounce = ExecuteMsg::RenounceOwnership {};
+
+            // Nobody could unpause afterwards, so the game's prize and ticket payments would be stuck
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &ExecuteMsg::PauseGame { game_id: 1 }, &[])
+                .unwrap();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &renounce, &[]).unwrap_err();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &ExecuteMsg::UnpauseGame { game_id: 1 }, &[])
+                .unwrap();
+
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &ExecuteMsg::Pause {}, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &renounce, &[]).unwrap_err();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &ExecuteMsg::Unpause {}, &[]).unwrap();
+
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &renounce, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle, &ExecuteMsg::EnterRaffle { game_id: 1 }, &coins(100, "usei"))
+                .unwrap();
+        }
+
+        #[test]
+        fn ticket_payments_cant_be_withdrawn() {
+            let (mut app, raffle, _proxy) = setup();
+
+            let withdraw = ExecuteMsg::TransferTokensToCollectionWallet {
+                amount: 1,
+                asset: AssetInfo::Native { denom: "usei".to_string() },
+                collection_wallet_address: ADMIN.to_string(),
+            };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &withdraw, &[]).unwrap_err();
+
+            let treasury: TreasuryResponse = app.wrap().query_wasm_smart(raffle, &QueryMsg::GetTreasury {}).unwrap();
+            assert_eq!(treasury.assets[0].balance, Uint128::new(300));
+            assert_eq!(treasury.assets[0].liabilities, Uint128::new(300));
+            assert!(treasury.assets[0].withdrawable.is_zero());
+        }
+
+        #[test]
+        fn only_proxy_can_deliver_randomness() {
+            let (mut app, raffle, _proxy) = setup();
+
+            let callback = ExecuteMsg::NoisReceive {
+                callback: NoisCallback {
+                    job_id: "1".to_string(),
+                    published: app.block_info().time,
+                    randomness: HexBinary::from_hex(RANDOMNESS).unwrap(),
+                },
+            };
+            app.execute_contract(Addr::unchecked(USER), raffle, &callback, &[]).unwrap_err();
+        }
+
+        #[test]
+        fn paused_raffle_stops_sales_but_refunds() {
+            let (mut app, raffle, _proxy) = setup();
+
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::PauseGame { game_id: 1 }, &[])
+                .unwrap_err();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &ExecuteMsg::PauseGame { game_id: 1 }, &[])
+                .unwrap();
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetGameInfo { game_id: 1 }).unwrap();
+            assert!(game.paused);
+            assert_eq!(game.status, GameStatus::Paused);
+
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id: 1 }, &coins(100, "usei"))
+                .unwrap_err();
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                raffle.clone(),
+                &ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id: 1, secret: None, beacon: None },
+                &[],
+            )
+            .unwrap_err();
+
+            // The pricing mistake is fixed by cancelling, buyers get their money back
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &ExecuteMsg::CancelRaffle { game_id: 1 }, &[])
+                .unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::ClaimRefund { game_id: 1 }, &[])
+                .unwrap();
+            let balance = app.wrap().query_balance(USER, "usei").unwrap();
+            assert_eq!(balance.amount, Uint128::new(1000));
+
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &ExecuteMsg::Pause {}, &[]).unwrap();
+            let global: GlobalResponse = app.wrap().query_wasm_smart(raffle, &QueryMsg::GetGlobalInfo {}).unwrap();
+            assert!(global.paused);
+        }
+
+        #[test]
+        fn randomness_delivered_while_paused_is_drawn_later() {
+            let (mut app, raffle, _proxy) = setup();
+            let silent_id = app.store_code(Box::new(ContractWrapper::new(silent_proxy_execute, noop_instantiate, noop_query)));
+            let silent = app
+                .instantiate_contract(silent_id, Addr::unchecked(ADMIN), &Empty {}, &[], "silent", None)
+                .unwrap();
+            let settings = ExecuteMsg::UpdateConfig(ConfigUpdate { randomness_proxy: Some(silent.to_string()), ..ConfigUpdate::default() });
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &settings, &[]).unwrap();
+            let start = ExecuteMsg::StartRaffle { params: raffle_params(&app), prize: PrizeItem::PotShare { bps: 5000 } };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id: 2 }, &coins(200, "usei"))
+                .unwrap();
+
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+            let finalize = ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id: 2, secret: None, beacon: None };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &finalize, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &ExecuteMsg::PauseGame { game_id: 2 }, &[])
+                .unwrap();
+
+            let callback = ExecuteMsg::NoisReceive {
+                callback: NoisCallback {
+                    job_id: "2".to_string(),
+                    published: app.block_info().time,
+                    randomness: HexBinary::from_hex(RANDOMNESS).unwrap(),
+                },
+            };
+            app.execute_contract(silent, raffle.clone(), &callback, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &finalize, &[]).unwrap_err();
+            // The held randomness is public, cancelling now would be a veto over the draw
+            app.update_block(|block| block.time = block.time.plus_seconds(2 * 24 * 60 * 60));
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &ExecuteMsg::CancelRaffle { game_id: 2 }, &[])
+                .unwrap_err();
+
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &ExecuteMsg::UnpauseGame { game_id: 2 }, &[])
+                .unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &finalize, &[]).unwrap();
+            let draw: VerifyDrawResponse = app.wrap().query_wasm_smart(raffle, &QueryMsg::VerifyDraw { game_id: 2 }).unwrap();
+            assert_eq!(draw.record.randomness, RANDOMNESS);
+            assert_eq!(draw.winners, vec![Addr::unchecked(USER)]);
+        }
+
+        #[test]
+        fn overdue_randomness_lets_operators_cancel() {
+            let (mut app, raffle, _proxy) = setup();
+            let silent_id = app.store_code(Box::new(ContractWrapper::new(silent_proxy_execute, noop_instantiate, noop_query)));
+            let silent = app
+                .instantiate_contract(silent_id, Addr::unchecked(ADMIN), &Empty {}, &[], "silent", None)
+                .unwrap();
+            let settings = ExecuteMsg::UpdateConfig(ConfigUpdate { randomness_proxy: Some(silent.to_string()), ..ConfigUpdate::default() });
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &settings, &[]).unwrap();
+            let start = ExecuteMsg::StartRaffle { params: raffle_params(&app), prize: PrizeItem::PotShare { bps: 5000 } };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id: 2 }, &coins(200, "usei"))
+                .unwrap();
+
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+            let finalize = ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id: 2, secret: None, beacon: None };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &finalize, &[]).unwrap();
+            let cancel = ExecuteMsg::CancelRaffle { game_id: 2 };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &cancel, &[]).unwrap_err();
+
+            // The proxy never answered within a day
+            app.update_block(|block| block.time = block.time.plus_seconds(24 * 60 * 60));
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &cancel, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::ClaimRefund { game_id: 2 }, &[])
+                .unwrap();
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle, &QueryMsg::GetGameInfo { game_id: 2 }).unwrap();
+            assert_eq!(game.status, GameStatus::Cancelled);
+        }
+
+        #[test]
+        fn winner_is_drawn_with_drand_beacon() {
+            let (mut app, raffle, _proxy) = setup();
+            let params = RaffleParams { draw_mode: DrawMode::Drand, ..raffle_params(&app) };
+            // Shifted genesis, so the recorded round is the first one published after end_time
+            let genesis_time = params.end_time / 1000 - (ROUND - 2) * PERIOD - PERIOD / 2;
+            let drand = |genesis_time| DrandConfig {
+                public_key: Binary::from(hex::decode(PUBLIC_KEY).unwrap()),
+                genesis_time,
+                period: PERIOD,
+            };
+            let settings = ExecuteMsg::UpdateConfig(ConfigUpdate { drand: Some(drand(genesis_time)), ..ConfigUpdate::default() });
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &settings, &[]).unwrap();
+            let start = ExecuteMsg::StartRaffle { params, prize: PrizeItem::PotShare { bps: 5000 } };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id: 2 }, &coins(200, "usei"))
+                .unwrap();
+
+            // Moving the genesis afterwards doesn't change the round the game waits for
+            let settings = ExecuteMsg::UpdateConfig(ConfigUpdate { drand: Some(drand(GENESIS_TIME)), ..ConfigUpdate::default() });
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &settings, &[]).unwrap();
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+
+            let finalize = |round, signature: &str| ExecuteMsg::SelectWinnerAndTransferNFTtoWinner {
+                game_id: 2,
+                secret: None,
+                beacon: Some(DrandBeacon {
+                    round,
+                    previous_signature: Binary::from(hex::decode(PREVIOUS_SIGNATURE).unwrap()),
+                    signature: Binary::from(hex::decode(signature).unwrap()),
+                }),
+            };
+            let err = app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &finalize(ROUND + 1, SIGNATURE), &[]).unwrap_err();
+            assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::WrongDrandRound { expected: ROUND })));
+            let err = app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &finalize(ROUND, PREVIOUS_SIGNATURE), &[]).unwrap_err();
+            assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::InvalidBeacon {})));
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &finalize(ROUND, SIGNATURE), &[]).unwrap();
+
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle.clone(), &QueryMsg::GetGameInfo { game_id: 2 }).unwrap();
+            assert_eq!(game.status, GameStatus::Ended);
+            assert_eq!(game.winners, vec![Addr::unchecked(USER)]);
+            let draw: VerifyDrawResponse = app.wrap().query_wasm_smart(raffle, &QueryMsg::VerifyDraw { game_id: 2 }).unwrap();
+            assert!(draw.valid);
+            assert_eq!(draw.record.randomness, RANDOMNESS);
+            assert_eq!(draw.record.beacon.map(|beacon| beacon.round), Some(ROUND));
+        }
+
+        #[test]
+        fn pause_stops_the_reveal_clock() {
+            let (mut app, raffle, _proxy) = setup();
+            let start = ExecuteMsg::StartRaffle { params: commit_reveal_params(&app), prize: PrizeItem::PotShare { bps: 5000 } };
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &start, &[]).unwrap();
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::EnterRaffle { game_id: 2 }, &coins(100, "usei"))
+                .unwrap();
+
+            // Paused for two days after end_time, longer than the reveal window
+            app.update_block(|block| block.time = block.time.plus_seconds(120));
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &ExecuteMsg::Pause {}, &[]).unwrap();
+            app.update_block(|block| block.time = block.time.plus_seconds(2 * 24 * 60 * 60));
+            app.execute_contract(Addr::unchecked(USER), raffle.clone(), &ExecuteMsg::ClaimRefund { game_id: 2 }, &[])
+                .unwrap_err();
+            app.execute_contract(Addr::unchecked(ADMIN), raffle.clone(), &ExecuteMsg::Unpause {}, &[]).unwrap();
+
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                raffle.clone(),
+                &ExecuteMsg::SelectWinnerAndTransferNFTtoWinner { game_id: 2, secret: Some(SECRET.to_string()), beacon: None },
+                &[],
+            )
+            .unwrap();
+            let game: GameResponse = app.wrap().query_wasm_smart(raffle, &QueryMsg::GetGameInfo { game_id: 2 }).unwrap();
+            assert_eq!(game.status, GameStatus::Ended);
+            assert_eq!(game.winners, vec![Addr::unchecked(USER)]);
+        }
+    }
+
+    mod drand {
+        use crate::drand::{beacon_randomness, first_round_after, is_valid_public_key, verify_beacon};
+
+        // League of Entropy mainnet, round 72785
+        pub const PUBLIC_KEY: &str = "868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31";
+        pub const GENESIS_TIME: u64 = 1595431050;
+        pub const PERIOD: u64 = 30;
+        pub const ROUND: u64 = 72785;
+        pub const PREVIOUS_SIGNATURE: &str = "a609e19a03c2fcc559e8dae14900aaefe517cb55c840f6e69bc8e4f66c8d18e8a609685d9917efbfb0c37f058c2de88f13d297c7e19e0ab24813079efe57a182554ff054c7638153f9b26a60e7111f71a0ff63d9571704905d3ca6df0b031747";
+        pub const SIGNATURE: &str = "82f5d3d2de4db19d40a6980e8aa37842a0e55d1df06bd68bddc8d60002e8e959eb9cfa368b3c1b77d18f02a54fe047b80f0989315f83b12a74fd8679c4f12aae86eaf6ab5690b34f1fddd50ee3cc6f6cdf59e95526d5a5d82aaa84fa6f181e42";
+        const RANDOMNESS: &str = "8b676484b5fb1f37f9ec5c413d7d29883504e5b669f604a1ce68b3388e9ae3d9";
+
+        #[test]
+        fn verifies_recorded_round() {
+            let public_key = hex::decode(PUBLIC_KEY).unwrap();
+            let previous_signature = hex::decode(PREVIOUS_SIGNATURE).unwrap();
+            let signature = hex::decode(SIGNATURE).unwrap();
+
+            assert!(verify_beacon(&public_key, ROUND, &previous_signature, &signature));
+            assert!(!verify_beacon(&public_key, ROUND + 1, &previous_signature, &signature));
+            assert!(!verify_beacon(&public_key, ROUND, &signature, &signature));
+            assert_eq!(hex::encode(beacon_randomness(&signature)), RANDOMNESS);
+        }
+
+        #[test]
+        fn rejects_malformed_public_key() {
+            let public_key = hex::decode(PUBLIC_KEY).unwrap();
+
+            assert!(is_valid_public_key(&public_key));
+            assert!(!is_valid_public_key(&public_key[1..]));
+            assert!(!is_valid_public_key(&[0u8; 48]));
+        }
+
+        #[test]
+        fn picks_first_round_after_end_time() {
+            let round_time = (GENESIS_TIME + (ROUND - 1) * PERIOD) * 1000;
+
+            assert_eq!(first_round_after(round_time - 1, GENESIS_TIME, PERIOD), ROUND);
+            assert_eq!(first_round_after(round_time, GENESIS_TIME, PERIOD), ROUND + 1);
+            assert_eq!(first_round_after(0, GENESIS_TIME, PERIOD), 1);
+        }
+    }
 }
@@ -4,40 +4,150 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_std::Addr;
 use cosmwasm_std::Binary;
 use cosmwasm_std::Coin;
+use cosmwasm_std::{HexBinary, Timestamp, Uint128};
+use cw20::Cw20ReceiveMsg;
+use cw721::Cw721ReceiveMsg;
 
-use crate::state::GameState;
+use crate::state::{AssetAmount, AssetInfo, DrandConfig, DrawMode, DrawRecord, GameState, GameStatus, PayoutShare, Prize, PrizeItem, Role, TicketPrice, VaultNft};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
-    pub authkey: String,
-    pub owner: Addr
+    pub admin: String,
+    pub default_denom: String,
+    pub platform_fee_bps: u64,
+    pub fee_recipient: Option<String>, // Defaults to the admin
+    pub creation_fee: Option<Coin>,
+    pub min_duration: u64, // Milliseconds
+    pub max_duration: u64,
+    pub drand: Option<DrandConfig>,
+    pub randomness_proxy: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DrandBeacon {
+    pub round: u64,
+    pub previous_signature: Binary,
+    pub signature: Binary,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    ReceiveNft {
-        sender: String,
-        token_id: String,
-        msg: Binary,
-    },
-    StartRaffle {
-        ticket_price: u64,
-        total_ticket_count: u64,
-        nft_contract_addr: Addr,
-        nft_token_id: String,
-        collection_wallet: Addr, // Collection wallet address to send tokens after the game finished
-        end_time: u64,
-    },
+    ReceiveNft(Cw721ReceiveMsg), // Sending an NFT with a ReceiveNftMsg escrows it as the prize of a new raffle
+    Receive(Cw20ReceiveMsg), // Enters a raffle paid with CW20 tokens or escrows CW20 prizes through a Cw20HookMsg
+    StartRaffle { params: RaffleParams, prize: PrizeItem }, // Native token prizes are sent along, pot shares need no escrow
+    AddPrize { game_id: u64, place: Option<u64>, prize: PrizeItem },
     EnterRaffle {
         game_id: u64
     },
     TransferTokensToCollectionWallet {
         amount: u128,
-        denom: String,
+        asset: AssetInfo,
         collection_wallet_address: String,
     },
-    SelectWinnerAndTransferNFTtoWinner { game_id: u64 },
+    SelectWinnerAndTransferNFTtoWinner {
+        game_id: u64,
+        secret: Option<String>, // CommitReveal games
+        beacon: Option<DrandBeacon>, // Drand games
+    },
+    WithdrawFromVault { pool: String, contract_addr: String, token_id: String }, // Only NFTs not reserved by a mystery box
+    UpdateConfig(ConfigUpdate), // Admin only
+    ProposeNewOwner { owner: String, expires_at: Option<u64> }, // The proposed admin has to accept before it takes over
+    AcceptOwnership {},
+    RenounceOwnership {}, // Leaves the contract without an admin for good and revokes every role, refused while anything is paused
+    Pause {}, // Every game, Role::Admin
+    Unpause {},
+    PauseGame { game_id: u64 }, // Role::Operator
+    UnpauseGame { game_id: u64 },
+    GrantRole { address: String, role: Role },
+    RevokeRole { address: String, role: Role },
+    AddAllowedCollection { contract_addr: String },
+    RemoveAllowedCollection { contract_addr: String },
+    AddAllowedToken { contract_addr: String }, // cw20 contracts accepted as prizes
+    RemoveAllowedToken { contract_addr: String },
+    DepositCreationCredit {}, // Prepays creation fees, raffles started through SendNft can't carry funds
+    WithdrawCreationCredit { denom: String },
+    CancelRaffle { game_id: u64 }, // The owner only before the first sale, operators until the draw or once the proxy callback is overdue
+    ClaimRefund { game_id: u64 },
+    NoisReceive { callback: NoisCallback },
+}
+
+// Fields left out keep their current value
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ConfigUpdate {
+    pub default_denom: Option<String>,
+    pub platform_fee_bps: Option<u64>, // Only raffles started afterwards pay the new fee
+    pub fee_recipient: Option<String>,
+    pub creation_enabled: Option<bool>,
+    pub creation_fee: Option<Coin>, // A zero amount removes the fee
+    pub min_duration: Option<u64>,
+    pub max_duration: Option<u64>,
+    pub drand: Option<DrandConfig>, // An empty public key removes drand, games already started keep theirs
+    pub randomness_proxy: Option<String>, // An empty address removes the proxy, games already started keep theirs
+}
+
+// Embedded in the `msg` of a cw721 SendNft to this contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveNftMsg {
+    StartRaffle(RaffleParams),
+    AddPrize { game_id: u64, place: Option<u64> }, // Adds the NFT to the bundle of `place`, or as the next placement, before any ticket is sold
+    DepositToVault { pool: String }, // Admin only, the NFT can then be won through a mystery box of the pool
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RaffleParams {
+    pub prices: Vec<TicketPrice>, // e.g. 10 usei or 25 of an IBC denom or 500 of a CW20
+    pub total_ticket_count: u64,
+    pub collection_wallet: String, // Collection wallet address to send tokens after the game finished
+    pub end_time: u64,
+    pub min_tickets_sold: Option<u64>, // At most total_ticket_count
+    pub reserve_amount: Option<Uint128>, // Counted as sold tickets times the first price, whatever asset they were paid with
+    pub draw_mode: DrawMode,
+    pub secret_hash: Option<String>, // Hex encoded sha256 of a secret the creator reveals after end_time, required for CommitReveal, which only operators can start
+    pub payout_split: Option<Vec<PayoutShare>>, // e.g. creator 70, collaborator 20, charity 10
+}
+
+// Embedded in the `msg` of a cw20 Send to this contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    EnterRaffle { game_id: u64 },
+    StartRaffle(RaffleParams), // The sent tokens are the prize, only for allowed tokens
+    AddPrize { game_id: u64, place: Option<u64> },
+}
+
+// Callback sent by the randomness proxy once the randomness of a job is published
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NoisCallback {
+    pub job_id: String,
+    pub published: Timestamp,
+    pub randomness: HexBinary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyExecuteMsg {
+    GetNextRandomness { job_id: String },
+}
+
+// cw2981 royalty query, sent to the prize NFT contract through the cw721 Extension query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw2981QueryMsg {
+    Extension { msg: Cw2981Extension },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw2981Extension {
+    RoyaltyInfo { token_id: String, sale_price: Uint128 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoyaltiesInfoResponse {
+    pub address: String,
+    pub royalty_amount: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -45,30 +155,85 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     // GetCount returns the current count as a json-encoded number
     GetGlobalInfo {},
+    GetConfig {},
+    GetPendingOwner {},
+    GetRoles { start_after: Option<String>, limit: Option<u32> },
     GetGameInfo { game_id: u64 },
     GetTicketsForWallet { game_id: u64, wallet_addr: Addr },
     GetAllGames {},
-    GetBalance {},
+    GetBalance { denom: Option<String> }, // Defaults to the configured default_denom
+    VerifyDraw { game_id: u64 },
+    GetVault {},
+    GetTreasury {},
+    GetCreationCredit { wallet: Addr, denom: String },
+    GetAllowedCollections { start_after: Option<String>, limit: Option<u32> },
+    GetAllowedTokens { start_after: Option<String>, limit: Option<u32> },
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct GlobalResponse {
     pub raffle_count: u64,
-    pub owner: Addr,
+    pub owner: Option<Addr>, // None once ownership was renounced
+    pub paused: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoleAssignment {
+    pub address: Addr,
+    pub roles: Vec<Role>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RolesResponse {
+    pub roles: Vec<RoleAssignment>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwnerResponse {
+    pub pending_owner: Option<Addr>,
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowedCollectionsResponse {
+    pub collections: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowedTokensResponse {
+    pub tokens: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreationCreditResponse {
+    pub credit: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct GameResponse {
-    pub ticket_price: u64,
+    pub prices: Vec<TicketPrice>,
+    pub proceeds: Vec<AssetAmount>, // Held by the contract
+    pub paid_out: Vec<AssetAmount>, // Sent to the collection wallet
     pub sold_ticket_count: u64,
     pub total_ticket_count: u64,
     pub raffle_status: u8,
-    pub nft_contract_addr: Addr,
-    pub nft_token_id: String,
+    pub status: GameStatus,
+    pub paused: bool, // Paused itself or through the global switch
+    pub prizes: Vec<Prize>,
+    pub winners: Vec<Addr>, // By placement, empty until the draw
     pub owner: Addr,
     pub collection_wallet: Addr,
+    pub payout_split: Vec<PayoutShare>,
+    pub platform_fee_bps: u64,
     pub end_time: u64,
+    pub min_tickets_sold: Option<u64>,
+    pub reserve_amount: Option<Uint128>,
+    pub draw_mode: DrawMode,
+    pub drand: Option<DrandConfig>,
+    pub randomness_proxy: Option<Addr>,
+    pub secret_hash: String,
+    pub entropy: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -84,4 +249,39 @@ pub struct AllGamesResponse {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BalanceResponse {
     pub balance: Coin,
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TreasuryAsset {
+    pub asset: AssetInfo,
+    pub balance: Uint128,
+    pub liabilities: Uint128, // Ticket payments and escrowed token prizes of games that aren't settled
+    pub withdrawable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TreasuryResponse {
+    pub assets: Vec<TreasuryAsset>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VaultPoolResponse {
+    pub pool: String,
+    pub nfts: Vec<VaultNft>, // Remaining contents, including the reserved ones
+    pub reserved: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VaultResponse {
+    pub pools: Vec<VaultPoolResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VerifyDrawResponse {
+    pub record: DrawRecord,
+    pub draw_mode: DrawMode, // Where record.randomness came from
+    pub secret_hash: String, // Commitment the randomness of a CommitReveal game must hash to
+    pub winner_indexes: Vec<u64>, // Recomputed from the recorded inputs
+    pub winners: Vec<Addr>, // Holders of the recomputed tickets
+    pub valid: bool,
+}
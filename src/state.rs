@@ -1,48 +1,211 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use cosmwasm_std::{Addr, Binary, Coin, Timestamp, Uint128};
+use cw_storage_plus::{Item, Map};
 
+// Values stored in `GameState.raffle_status`
+pub const RAFFLE_ENDED: u8 = 0;
+pub const RAFFLE_ACTIVE: u8 = 1;
+pub const RAFFLE_REFUNDING: u8 = 2;
+pub const RAFFLE_DRAWING: u8 = 3; // Waiting for the randomness proxy callback
+pub const RAFFLE_NO_SALES: u8 = 4; // Ended without a single ticket sold, so there is no winner
+pub const RAFFLE_CANCELLED: u8 = 5; // Cancelled by its owner or the admin, buyers claim refunds
 
-use cosmwasm_std::Addr;
-use cw_storage_plus::{Item, Map};
-use cw_storage_plus::{Item, Map};
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub enum GameStatus {
+    Active,
+    Paused, // Active or time over, but entries and finalization are stopped
+    Ended,
+    TimeOver,
+    Refunding,
+    Drawing,
+    NoSales,
+    Cancelled,
+}
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfo {
+    Native { denom: String }, // Any bank denom, including IBC and tokenfactory denoms
+    Cw20 { contract_addr: Addr },
+}
 
-// use cosmwasm_std::Addr;
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetAmount {
+    pub asset: AssetInfo,
+    pub amount: Uint128,
+}
 
-use cw_storage_plus::{Item, Map};
+// Price of one ticket when paid with `asset`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TicketPrice {
+    pub asset: AssetInfo,
+    pub price: Uint128,
+}
 
-// use cw_storage_plus::{Item, Map};
+// One part of a prize, NFTs and token amounts are escrowed until the draw
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PrizeItem {
+    Nft { contract_addr: Addr, token_id: String },
+    Tokens { asset: AssetInfo, amount: Uint128 },
+    PotShare { bps: u64 }, // Share of the ticket sales in every payment asset, 10000 is the whole pot
+    MysteryBox { pool: String }, // A random NFT of the vault pool, replaced by the NFT it turned out to be at the draw
+}
 
+// Everything a placement wins, the whole bundle goes to the same winner
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Prize {
+    pub items: Vec<PrizeItem>,
+}
 
+// NFT deposited by the admin into a vault pool
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub enum GameStatus {
-    Active,
-    Ended,
-    TimeOver,
+pub struct VaultNft {
+    pub contract_addr: Addr,
+    pub token_id: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VaultPool {
+    pub nfts: Vec<VaultNft>,
+    pub reserved: u64, // Mystery boxes of undrawn raffles, each one is owed an NFT of the pool
+}
+
+// How the randomness for the winner selection is provided
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DrawMode {
+    CommitReveal, // The creator reveals the secret committed at StartRaffle, operators only since the creator can predict the winner
+    Drand, // Anyone submits the first drand beacon round published after end_time
+    Proxy, // The randomness proxy contract delivers the randomness in a callback
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DrandConfig {
+    pub public_key: Binary, // Compressed G1 public key of the drand network
+    pub genesis_time: u64, // Seconds
+    pub period: u64, // Seconds between two rounds
+}
+
+// Recipient of a weighted part of a game's proceeds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PayoutShare {
+    pub recipient: Addr,
+    pub weight: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub admin: Option<Addr>, // None once ownership was renounced
+    pub default_denom: String, // Denom of the balance query when none is given
+    pub platform_fee_bps: u64, // Taken from the proceeds of every drawn game
+    pub fee_recipient: Addr,
+    pub creation_enabled: bool, // Whether wallets other than the admin can start raffles
+    pub creation_fee: Option<Coin>, // Charged per raffle to creators other than the admin, out of their prepaid credit
+    pub min_duration: u64, // Milliseconds between raffle creation and end_time, like end_time
+    pub max_duration: u64,
+    pub drand: Option<DrandConfig>, // Required to start Drand games
+    pub randomness_proxy: Option<Addr>, // Required to start Proxy games
+}
+
+// Delegated permissions, the config admin holds all of them and renouncing ownership revokes them
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin, // Manages roles and the collection allowlist, and holds every other role
+    Operator, // Starts and cancels raffles and manages the vault
+    Treasurer, // Withdraws the free balance
+    Keeper, // Finalizes raffles
+}
+
+// Admin proposed by the current one, it only takes over once it accepts
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwner {
+    pub owner: Addr,
+    pub expires_at: Option<u64>, // Milliseconds, like end_time
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct GlobalState {
     pub count: u64,
-    pub owner: Addr
+    pub paused_since: Option<u64>, // Milliseconds, set while entries and finalization of every game are stopped
+    pub paused_time: u64, // Milliseconds spent paused before the current pause
 }
 
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct GameState {
-    pub ticket_price: u64,
+    pub prices: Vec<TicketPrice>, // Accepted payment assets, the first one is the reference for reserve_amount
     pub sold_ticket_count: u64,
     pub total_ticket_count: u64,
     pub raffle_status: u8,
-    pub nft_contract_addr: Addr,
-    pub nft_token_id: String,
+    pub prizes: Vec<Prize>, // Escrowed prizes by placement, the first drawn ticket wins the first one
     pub owner: Addr,
     pub collection_wallet: Addr, // Collection wallet address to send tokens after the game finished
+    pub payout_split: Vec<PayoutShare>, // Replaces the collection wallet as proceeds recipient when set
+    pub platform_fee_bps: u64, // Platform fee when the game was created, later config updates don't apply to it
     pub end_time: u64,
+    pub min_tickets_sold: Option<u64>, // Below this the raffle is refunded instead of drawn
+    pub reserve_amount: Option<Uint128>, // Same as min_tickets_sold, expressed in the first price's asset
+    pub draw_mode: DrawMode,
+    pub drand: Option<DrandConfig>, // Network of a Drand game, taken from the config at creation so updates can't move its round
+    pub randomness_proxy: Option<Addr>, // Proxy of a Proxy game, taken from the config at creation, its callback is only accepted from it
+    pub secret_hash: String, // Hex encoded sha256 of the creator's secret, empty unless draw_mode is CommitReveal
+    pub entropy: String, // Running hash over all ticket purchases, mixed with the external randomness
+    pub randomness_requested_at: Option<u64>, // Milliseconds, when a Proxy game asked the proxy for its randomness
+    pub paused_since: Option<u64>, // Milliseconds, set while entries and finalization of this game are stopped, refunds and cancelling still work
+    pub paused_time: u64, // Milliseconds this game spent paused before the current pause
+    pub global_paused_offset: u64, // Global paused time when the game was created, earlier pauses don't concern it
+}
+
+// Drand round a game was drawn with, so its signature can be checked again
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DrawBeacon {
+    pub public_key: Binary, // Of the drand network the game was started with
+    pub round: u64,
+    pub previous_signature: Binary,
+    pub signature: Binary,
+}
+
+// Randomness delivered by the proxy for a game
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DrawCallback {
+    pub job_id: String,
+    pub published: Timestamp,
+    pub randomness: String, // Hex encoded
+}
+
+// Every input of a draw, so anyone can replay the winner selection
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DrawRecord {
+    pub block_height: u64,
+    pub block_time: u64, // Nanoseconds
+    pub sold_ticket_count: u64,
+    pub entropy: String, // Purchase entropy of the game at draw time
+    pub randomness: String, // External entropy: revealed secret, drand or proxy randomness
+    pub beacon: Option<DrawBeacon>, // Drand games
+    pub callback: Option<DrawCallback>, // Proxy games
+    pub winner_indexes: Vec<u64>, // Winning ticket index of each placement
+    pub winners: Vec<Addr>,
 }
 
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new("pending_owner");
 pub const GLOBAL_STATE: Item<GlobalState> = Item::new("global_state");
+pub const ROLES: Map<Addr, Vec<Role>> = Map::new("roles");
 pub const GAME_STATE: Map<u64, GameState> = Map::new("game_state");
 pub const TICKET_STATUS: Map<(u64, u64), Addr> = Map::new("ticket_status");
 pub const WALLET_TICKETS: Map<(u64, Addr), Vec<u64>> = Map::new("wallet_tickets");
+pub const REFUND_CLAIMED: Map<(u64, Addr), bool> = Map::new("refund_claimed");
+pub const DRAW_RECORDS: Map<u64, DrawRecord> = Map::new("draw_records");
+pub const LIABILITIES: Item<Vec<AssetAmount>> = Item::new("liabilities"); // Everything the contract owes to buyers, winners and creators
+pub const GAME_PROCEEDS: Map<u64, Vec<AssetAmount>> = Map::new("game_proceeds"); // Ticket revenue held for each game
+pub const GAME_PAYOUTS: Map<u64, Vec<AssetAmount>> = Map::new("game_payouts"); // Revenue paid out when the game was drawn, platform fee included
+pub const CREATION_CREDITS: Map<(Addr, String), Uint128> = Map::new("creation_credits"); // Prepaid creation fees by creator and denom
+pub const ALLOWED_COLLECTIONS: Map<Addr, bool> = Map::new("allowed_collections"); // cw721 contracts accepted as prizes
+pub const ALLOWED_TOKENS: Map<Addr, bool> = Map::new("allowed_tokens"); // cw20 contracts accepted as prizes
+pub const VAULT: Map<&str, VaultPool> = Map::new("vault"); // Mystery box pools by name
+pub const WALLET_PAYMENTS: Map<(u64, Addr), Vec<AssetAmount>> = Map::new("wallet_payments"); // What each buyer paid, for refunds
+pub const PENDING_RANDOMNESS: Map<u64, DrawCallback> = Map::new("pending_randomness"); // Proxy randomness delivered while the game was paused